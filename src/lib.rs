@@ -7,13 +7,28 @@
  * @summary
  * This smart contract implements a simple vesting mechanism on the Solana blockchain.
  * It allows a funder to lock a specific amount of tokens in a vault, which will be released
- * to a designated recipient after a predefined vesting period.
+ * to a designated recipient over a predefined vesting period.
  *
  * @details
  * - The `init_vesting` function initializes the vesting schedule, transferring tokens from the funder to a vault.
- * - The `claim_vesting` function allows the recipient to claim the vested tokens once the vesting period has ended.
- * - The vesting schedule is immutable once set; neither the amount nor the recipient can be changed.
- * - Token transfers are handled using the SPL Token program.
+ * - The `claim_vesting` function allows the recipient to claim the tokens vested so far: an optional TGE
+ *   portion unlocked at init, plus a linear stream of the remainder until the vesting period ends.
+ * - The vesting schedule is immutable once set, except through `split_vesting`/`merge_vesting`, the
+ *   funder's `revoke_partial` of unvested tokens and the opt-in, delayed `recover` flow that
+ *   redirects the schedule to a new recipient.
+ * - The funder can pause a schedule, stopping its vesting clock and extending its end, or freeze
+ *   it, halting all operations without changing its timing.
+ * - Self-vesting, where the funder is also the recipient, is supported for personal lockups.
+ * - Token transfers are handled using the SPL Token program. Mints and vaults are read with the
+ *   base SPL Token layout, so Token-2022 mints carrying extensions (e.g. interest-bearing or
+ *   transfer-fee mints) are not supported, and vaults never accumulate withheld transfer fees.
+ * - Vaults are owned by a vault authority PDA (`[b"vault-auth", vesting_state]`) that signs all
+ *   transfers out of them.
+ * - Protocol-wide parameters (claim fee, treasury, allowed token programs, minimum duration,
+ *   per-funder schedule cap, KYC verifier) live in a singleton config PDA (`[b"config"]`) that
+ *   only the program's upgrade authority can change.
+ * - With a per-funder schedule cap, each funder's open schedules are counted in a funder quota PDA
+ *   (`[b"funder-quota", funder]`).
  *
  * @authors
  * - Scarcity-pretend (Spxc)
@@ -22,18 +37,30 @@
  * - 2024-06-08: Added vesting and claim functionality.
  * - 2024-06-04: Initial version
  */
+#[cfg(feature = "profiling")]
+use solana_program::compute_units::sol_remaining_compute_units;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable,
     clock::Clock,
     entrypoint,
     entrypoint::ProgramResult,
+    hash::{hashv, Hash},
+    instruction::{get_stack_height, AccountMeta, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT},
     msg,
-    program::{invoke, invoke_signed},
+    program::{get_return_data, invoke, invoke_signed, set_return_data, MAX_RETURN_DATA},
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
+    pubkey,
     pubkey::Pubkey,
-    sysvar::{rent::Rent, Sysvar},
+    system_instruction,
+    sysvar::{
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+        rent::Rent,
+        Sysvar,
+    },
 };
+use std::ops::Range;
 
 // Define program states
 #[derive(Debug)]
@@ -44,23 +71,506 @@ pub struct VestingState {
     pub amount: u64,
     pub vesting_start: i64,
     pub vesting_end: i64,
+    pub claimed_amount: u64,
+    pub tge_bps: u16,
+    pub recovery_authority: Pubkey,
+    pub recovery_receiver: Pubkey,
+    pub recovery_requested_at: i64,
+    pub rate_per_second: u64,
+    pub last_claim_at: i64,
+    pub decimals: u8,
+    pub vault_authority_bump: u8,
+    pub claim_fee_bps: u16,
+    pub treasury: Pubkey,
+    pub mint: Pubkey,
+    pub claim_hook: Pubkey,
+    pub checkpoint_at: i64,
+    pub checkpoint_vested: u64,
+    pub step_count: u8,
+    pub claimed_milestones: u64,
+    pub paused_at: i64,
+    pub paused_duration: i64,
+    pub is_frozen: bool,
+    pub schedule_hash: Hash,
+    pub unit_size: u64,
+    pub dual_control: bool,
+    pub irrevocable: bool,
+    pub pending_funder: Pubkey,
+    pub refund_owner: Pubkey,
+    pub active: bool,
+    pub claim_expiry: i64,
+    pub quota_owner: Pubkey,
+    pub period_seconds: i64,
+    pub per_period: u64,
+    pub kyc_verifier: Pubkey,
+    pub lock_after_first_claim: bool,
+    pub launch: Pubkey,
+    pub launch_duration: i64,
+    pub auto_claim: bool,
+    pub curve_point_count: u8,
+    pub curve_points: [(i64, u64); MAX_CURVE_POINTS],
+    pub withhold_bps: u16,
+    pub tax_account: Pubkey,
+    pub allow_early_claim: bool,
+    pub early_claim_penalty_bps: u16,
+    pub claim_governance: Pubkey,
+    pub vault: Pubkey,
+}
+
+// Define program config
+#[derive(Debug)]
+pub struct ProgramConfig {
+    pub is_initialized: bool,
+    pub bump: u8,
+    pub claim_fee_bps: u16,
+    pub treasury: Pubkey,
+    pub allowed_token_programs: [Pubkey; MAX_ALLOWED_TOKEN_PROGRAMS],
+    pub min_duration: i64,
+    pub max_schedules_per_funder: u32,
+    pub kyc_verifier: Pubkey,
+    pub require_ata_vault: bool,
+    pub max_duration: i64,
+    pub deprecated: bool,
+}
+
+// Define funder quota
+#[derive(Debug)]
+pub struct FunderQuota {
+    pub is_initialized: bool,
+    pub bump: u8,
+    pub active_schedules: u32,
+}
+
+// Define claim ledger
+#[derive(Debug)]
+pub struct ClaimLedger {
+    pub account_type: u8,
+    pub bump: u8,
+    pub vesting_state: Pubkey,
+    pub next_index: u16,
+    pub entry_count: u16,
+    pub entries: [(i64, u64); CLAIM_LEDGER_CAPACITY],
+}
+
+// Define launch
+#[derive(Debug)]
+pub struct LaunchState {
+    pub is_initialized: bool,
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub launch_at: i64,
+}
+
+// Define program errors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum VestingError {
+    /// Nothing vested to claim
+    NothingToClaim = 0,
+    /// Vested tokens must be claimed before splitting
+    UnclaimedVestedTokens = 1,
+    /// Schedule parameters do not match
+    ScheduleMismatch = 2,
+    /// No recovery has been requested
+    NoRecoveryRequested = 3,
+    /// Recovery delay has not elapsed
+    RecoveryDelayNotElapsed = 4,
+    /// Mint decimals do not match the schedule
+    DecimalsMismatch = 5,
+    /// Arithmetic overflow
+    MathOverflow = 6,
+    /// Token accounts do not match the schedule mint
+    MintMismatch = 7,
+    /// Recipient token account is frozen
+    RecipientFrozen = 8,
+    /// Milestone has not been reached yet
+    MilestoneNotReached = 9,
+    /// Milestone has already been claimed
+    MilestoneAlreadyClaimed = 10,
+    /// Vault still holds tokens
+    VaultNotEmpty = 11,
+    /// Schedule is frozen
+    ScheduleFrozen = 12,
+    /// The same account was passed for distinct roles
+    DuplicateAccount = 13,
+    /// Vault has an active delegate
+    UnexpectedDelegate = 14,
+    /// Schedule is irrevocable
+    Irrevocable = 15,
+    /// Schedule is not fully claimed
+    NotFullyClaimed = 16,
+    /// Schedule has not been activated
+    NotActive = 17,
+    /// Claim window has expired
+    ClaimExpired = 18,
+    /// Funder has reached its schedule cap
+    FunderQuotaExceeded = 19,
+    /// KYC attestation does not attest the recipient
+    InvalidKycAttestation = 20,
+    /// KYC attestation has expired
+    KycAttestationExpired = 21,
+    /// Schedule has not started vesting yet
+    NothingVestedYet = 22,
+    /// Recipient is locked after the first claim
+    RecipientLocked = 23,
+    /// Launch timestamp has not been set
+    LaunchPending = 24,
+    /// Schedule exceeds the maximum duration
+    DurationTooLong = 25,
+    /// Program is deprecated and accepts no new schedules
+    Deprecated = 26,
+    /// Claim must be invoked by the governance program of the schedule
+    GovernanceRequired = 27,
+}
+
+/// Result of helpers that only fail with a `VestingError`, converted to a `ProgramError` with `?`.
+pub type VestingResult<T> = Result<T, VestingError>;
+
+impl From<VestingError> for ProgramError {
+    fn from(error: VestingError) -> Self {
+        ProgramError::Custom(error as u32)
+    }
+}
+
+// Define claim results
+#[derive(Debug, PartialEq)]
+pub struct ClaimOutcome {
+    pub transferred: u64,
+    pub total_claimed: u64,
+    pub is_final: bool,
+}
+
+impl ClaimOutcome {
+    /// Encodes the outcome as transferred (u64), total claimed (u64) and is final (u8).
+    pub fn to_bytes(&self) -> [u8; 17] {
+        let mut bytes = [0u8; 17];
+        bytes[0..8].copy_from_slice(&self.transferred.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.total_claimed.to_le_bytes());
+        bytes[16] = self.is_final as u8;
+        bytes
+    }
+}
+
+// Define instruction statuses
+#[derive(Debug, PartialEq)]
+pub struct InstructionStatus {
+    pub instruction: u8,
+    pub claimed_amount: u64,
+    pub remaining: u64,
+    pub flags: u8,
+}
+
+impl InstructionStatus {
+    /// Schedule is open, i.e. initialized and not closed out.
+    pub const OPEN: u8 = 1 << 0;
+    /// Schedule has been activated.
+    pub const ACTIVE: u8 = 1 << 1;
+    /// Schedule is paused.
+    pub const PAUSED: u8 = 1 << 2;
+    /// Schedule is frozen.
+    pub const FROZEN: u8 = 1 << 3;
+
+    /// Encodes the status as instruction (u8), claimed (u64), remaining (u64) and flags (u8).
+    pub fn to_bytes(&self) -> [u8; 18] {
+        let mut bytes = [0u8; 18];
+        bytes[0] = self.instruction;
+        bytes[1..9].copy_from_slice(&self.claimed_amount.to_le_bytes());
+        bytes[9..17].copy_from_slice(&self.remaining.to_le_bytes());
+        bytes[17] = self.flags;
+        bytes
+    }
+}
+
+// Define instruction parameters
+#[derive(Debug)]
+pub struct InitVestingParams {
+    pub amount: u64,
+    pub vesting_end: i64,
+    pub tge_bps: u16,
+    pub recovery_authority: Pubkey,
+    pub rate_per_second: u64,
+    pub claim_hook: Pubkey,
+    pub step_count: u8,
+    pub unit_size: u64,
+    pub amount_in_bps: bool,
+    pub dual_control: bool,
+    pub end_is_duration: bool,
+    pub irrevocable: bool,
+    pub multisig_signer_count: u8,
+    pub nft: bool,
+    pub claim_expiry: i64,
+    pub period_seconds: i64,
+    pub per_period: u64,
+    pub kyc_required: bool,
+    pub vesting_start: i64,
+    pub lock_after_first_claim: bool,
+    pub launch: Pubkey,
+    pub auto_claim: bool,
+    pub withhold_bps: u16,
+    pub tax_account: Pubkey,
+    pub allow_early_claim: bool,
+    pub early_claim_penalty_bps: u16,
+    pub claim_governance: Pubkey,
+    pub curve_points: Vec<(i64, u64)>,
+    pub co_funder_amounts: Vec<u64>,
+}
+
+#[derive(Debug)]
+pub struct ConfigParams {
+    pub claim_fee_bps: u16,
+    pub treasury: Pubkey,
+    pub allowed_token_programs: [Pubkey; MAX_ALLOWED_TOKEN_PROGRAMS],
+    pub min_duration: i64,
+    pub max_schedules_per_funder: u32,
+    pub kyc_verifier: Pubkey,
+    pub require_ata_vault: bool,
+    pub max_duration: i64,
+    pub deprecated: bool,
+}
+
+#[derive(Debug)]
+pub struct CurveParams {
+    pub start: i64,
+    pub end: i64,
+    pub sample_count: u8,
+}
+
+#[cfg(feature = "test-utils")]
+#[derive(Debug)]
+pub struct TestStateParams {
+    pub vesting_start: i64,
+    pub vesting_end: i64,
+    pub claimed_amount: u64,
+}
+
+/// Basis points denominator, 10000 bps == 100%.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Delay between a recovery request and its execution, in seconds (7 days).
+pub const RECOVERY_DELAY: i64 = 7 * 24 * 60 * 60;
+
+/// Maximum number of schedules `read_claimable_amounts` reports, bounded by the return data size.
+pub const MAX_BULK_CLAIMABLE: usize = MAX_RETURN_DATA / 8;
+
+/// Maximum number of `(timestamp, vested)` samples `read_vesting_curve` reports, bounded by the
+/// return data size.
+pub const MAX_CURVE_SAMPLES: usize = MAX_RETURN_DATA / 16;
+
+/// Maximum number of milestones `read_milestones` reports at once, bounded by the return data
+/// size.
+pub const MAX_MILESTONE_ENTRIES: usize = MAX_RETURN_DATA / 18;
+
+/// Seed prefix of the vault authority PDA, `[VAULT_AUTHORITY_SEED, vesting_state]`.
+pub const VAULT_AUTHORITY_SEED: &[u8] = b"vault-auth";
+
+/// Layout version of instruction payloads, the byte following the instruction byte.
+pub const INSTRUCTION_VERSION: u8 = 1;
+
+/// Seed of the singleton program config PDA, `[CONFIG_SEED]`.
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Seed prefix of the funder quota PDA, `[FUNDER_QUOTA_SEED, funder]`.
+pub const FUNDER_QUOTA_SEED: &[u8] = b"funder-quota";
+
+/// Seed prefix of the launch PDA, `[LAUNCH_SEED, authority]`.
+pub const LAUNCH_SEED: &[u8] = b"launch";
+
+/// Seed prefix of the claim ledger PDA, `[CLAIM_LEDGER_SEED, vesting_state]`.
+pub const CLAIM_LEDGER_SEED: &[u8] = b"ledger";
+
+/// Account type byte leading an initialized claim ledger.
+pub const CLAIM_LEDGER_ACCOUNT_TYPE: u8 = 1;
+
+/// Number of token program slots in the program config, unused slots hold the default pubkey.
+pub const MAX_ALLOWED_TOKEN_PROGRAMS: usize = 2;
+
+/// Share of a schedule's amount, in basis points, swept from the vault as dust on the final claim.
+pub const VAULT_DUST_BPS: u64 = 1;
+
+/// Maximum number of milestones of a stepped schedule, bounded by the `claimed_milestones` bitmap.
+pub const MAX_MILESTONES: u8 = 64;
+
+/// Maximum number of `(timestamp, cumulative_amount)` control points of a curve schedule.
+pub const MAX_CURVE_POINTS: usize = 8;
+
+/// Number of `(timestamp, amount)` claims a claim ledger keeps, older claims are evicted.
+pub const CLAIM_LEDGER_CAPACITY: usize = 32;
+
+/// Maximum length, in bytes, of the memo attached to a claim transfer.
+pub const MAX_CLAIM_MEMO_LEN: usize = 128;
+
+/// Program ID of the SPL Memo program, see `claim_vesting`.
+pub const SPL_MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// Program ID of the SPL associated token account program, see `find_associated_token_address`.
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/**
+ * Reads a fixed-size field out of packed account data.
+ *
+ * Packed layouts are read through this helper rather than by indexing, so account data shorter
+ * than the layout fails with `InvalidAccountData` instead of panicking.
+ *
+ * Parameters:
+ * - `src`: The packed account data.
+ * - `range`: The byte range of the field, `N` bytes long.
+ *
+ * Returns:
+ * - The field bytes.
+ */
+fn read_array<const N: usize>(src: &[u8], range: Range<usize>) -> Result<[u8; N], ProgramError> {
+    src.get(range)
+        .ok_or(ProgramError::InvalidAccountData)?
+        .try_into()
+        .map_err(|_| ProgramError::InvalidAccountData)
 }
 
 impl Sealed for VestingState {}
+
+/**
+ * On-chain layout of `VestingState`.
+ *
+ * This layout is the account ABI of deployed schedules, new fields are only ever appended.
+ * All integers are little-endian, booleans are a single `0`/`1` byte and pubkeys are their 32
+ * raw bytes:
+ *
+ * | Offset    | Size | Field                     |
+ * |-----------|------|---------------------------|
+ * | 0..32     | 32   | `receiver`                |
+ * | 32..64    | 32   | `funder`                  |
+ * | 64..72    | 8    | `amount`                  |
+ * | 72..80    | 8    | `vesting_start`           |
+ * | 80..88    | 8    | `vesting_end`             |
+ * | 88        | 1    | `is_initialized`          |
+ * | 89..97    | 8    | `claimed_amount`          |
+ * | 97..99    | 2    | `tge_bps`                 |
+ * | 99..131   | 32   | `recovery_authority`      |
+ * | 131..163  | 32   | `recovery_receiver`       |
+ * | 163..171  | 8    | `recovery_requested_at`   |
+ * | 171..179  | 8    | `rate_per_second`         |
+ * | 179..187  | 8    | `last_claim_at`           |
+ * | 187       | 1    | `decimals`                |
+ * | 188       | 1    | `vault_authority_bump`    |
+ * | 189..191  | 2    | `claim_fee_bps`           |
+ * | 191..223  | 32   | `treasury`                |
+ * | 223..255  | 32   | `mint`                    |
+ * | 255..287  | 32   | `claim_hook`              |
+ * | 287..295  | 8    | `checkpoint_at`           |
+ * | 295..303  | 8    | `checkpoint_vested`       |
+ * | 303       | 1    | `step_count`              |
+ * | 304..312  | 8    | `claimed_milestones`      |
+ * | 312..320  | 8    | `paused_at`               |
+ * | 320..328  | 8    | `paused_duration`         |
+ * | 328       | 1    | `is_frozen`               |
+ * | 329..361  | 32   | `schedule_hash`           |
+ * | 361..369  | 8    | `unit_size`               |
+ * | 369       | 1    | `dual_control`            |
+ * | 370       | 1    | `irrevocable`             |
+ * | 371..403  | 32   | `pending_funder`          |
+ * | 403..435  | 32   | `refund_owner`            |
+ * | 435       | 1    | `active`                  |
+ * | 436..444  | 8    | `claim_expiry`            |
+ * | 444..476  | 32   | `quota_owner`             |
+ * | 476..484  | 8    | `period_seconds`          |
+ * | 484..492  | 8    | `per_period`              |
+ * | 492..524  | 32   | `kyc_verifier`            |
+ * | 524       | 1    | `lock_after_first_claim`  |
+ * | 525..557  | 32   | `launch`                  |
+ * | 557..565  | 8    | `launch_duration`         |
+ * | 565       | 1    | `auto_claim`              |
+ * | 566       | 1    | `curve_point_count`       |
+ * | 567..695  | 128  | `curve_points`            |
+ * | 695..697  | 2    | `withhold_bps`            |
+ * | 697..729  | 32   | `tax_account`             |
+ * | 729       | 1    | `allow_early_claim`       |
+ * | 730..732  | 2    | `early_claim_penalty_bps` |
+ * | 732..764  | 32   | `claim_governance`        |
+ * | 764..796  | 32   | `vault`                   |
+ *
+ * `curve_points` holds `MAX_CURVE_POINTS` `(timestamp, cumulative_amount)` pairs of 16 bytes
+ * each, of which the first `curve_point_count` are in use.
+ */
 impl Pack for VestingState {
-    const LEN: usize = 97;
+    const LEN: usize = 796;
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let receiver_bytes: [u8; 32] = src[0..32].try_into().unwrap();
-        let funder_bytes: [u8; 32] = src[32..64].try_into().unwrap();
+        let receiver_bytes: [u8; 32] = read_array(src, 0..32)?;
+        let funder_bytes: [u8; 32] = read_array(src, 32..64)?;
+        let recovery_authority_bytes: [u8; 32] = read_array(src, 99..131)?;
+        let recovery_receiver_bytes: [u8; 32] = read_array(src, 131..163)?;
+        let treasury_bytes: [u8; 32] = read_array(src, 191..223)?;
+        let mint_bytes: [u8; 32] = read_array(src, 223..255)?;
+        let claim_hook_bytes: [u8; 32] = read_array(src, 255..287)?;
+        let schedule_hash_bytes: [u8; 32] = read_array(src, 329..361)?;
+        let pending_funder_bytes: [u8; 32] = read_array(src, 371..403)?;
+        let refund_owner_bytes: [u8; 32] = read_array(src, 403..435)?;
+        let quota_owner_bytes: [u8; 32] = read_array(src, 444..476)?;
+        let kyc_verifier_bytes: [u8; 32] = read_array(src, 492..524)?;
+        let launch_bytes: [u8; 32] = read_array(src, 525..557)?;
+        let tax_account_bytes: [u8; 32] = read_array(src, 697..729)?;
+        let claim_governance_bytes: [u8; 32] = read_array(src, 732..764)?;
+        let vault_bytes: [u8; 32] = read_array(src, 764..796)?;
+        let mut curve_points = [(0i64, 0u64); MAX_CURVE_POINTS];
+        for (i, point) in curve_points.iter_mut().enumerate() {
+            let offset = 567 + i * 16;
+            *point = (
+                i64::from_le_bytes(read_array(src, offset..offset + 8)?),
+                u64::from_le_bytes(read_array(src, offset + 8..offset + 16)?),
+            );
+        }
 
         Ok(VestingState {
-            is_initialized: true,
+            is_initialized: *src.get(88).ok_or(ProgramError::InvalidAccountData)? != 0,
             receiver: Pubkey::from(receiver_bytes),
             funder: Pubkey::from(funder_bytes),
-            amount: u64::from_le_bytes((&src[64..72]).try_into().unwrap()),
-            vesting_start: i64::from_le_bytes((&src[72..80]).try_into().unwrap()),
-            vesting_end: i64::from_le_bytes((&src[80..88]).try_into().unwrap()),
+            amount: u64::from_le_bytes(read_array(src, 64..72)?),
+            vesting_start: i64::from_le_bytes(read_array(src, 72..80)?),
+            vesting_end: i64::from_le_bytes(read_array(src, 80..88)?),
+            claimed_amount: u64::from_le_bytes(read_array(src, 89..97)?),
+            tge_bps: u16::from_le_bytes(read_array(src, 97..99)?),
+            recovery_authority: Pubkey::from(recovery_authority_bytes),
+            recovery_receiver: Pubkey::from(recovery_receiver_bytes),
+            recovery_requested_at: i64::from_le_bytes(read_array(src, 163..171)?),
+            rate_per_second: u64::from_le_bytes(read_array(src, 171..179)?),
+            last_claim_at: i64::from_le_bytes(read_array(src, 179..187)?),
+            decimals: *src.get(187).ok_or(ProgramError::InvalidAccountData)?,
+            vault_authority_bump: *src.get(188).ok_or(ProgramError::InvalidAccountData)?,
+            claim_fee_bps: u16::from_le_bytes(read_array(src, 189..191)?),
+            treasury: Pubkey::from(treasury_bytes),
+            mint: Pubkey::from(mint_bytes),
+            claim_hook: Pubkey::from(claim_hook_bytes),
+            checkpoint_at: i64::from_le_bytes(read_array(src, 287..295)?),
+            checkpoint_vested: u64::from_le_bytes(read_array(src, 295..303)?),
+            step_count: *src.get(303).ok_or(ProgramError::InvalidAccountData)?,
+            claimed_milestones: u64::from_le_bytes(read_array(src, 304..312)?),
+            paused_at: i64::from_le_bytes(read_array(src, 312..320)?),
+            paused_duration: i64::from_le_bytes(read_array(src, 320..328)?),
+            is_frozen: *src.get(328).ok_or(ProgramError::InvalidAccountData)? != 0,
+            schedule_hash: Hash::new_from_array(schedule_hash_bytes),
+            unit_size: u64::from_le_bytes(read_array(src, 361..369)?),
+            dual_control: *src.get(369).ok_or(ProgramError::InvalidAccountData)? != 0,
+            irrevocable: *src.get(370).ok_or(ProgramError::InvalidAccountData)? != 0,
+            pending_funder: Pubkey::from(pending_funder_bytes),
+            refund_owner: Pubkey::from(refund_owner_bytes),
+            active: *src.get(435).ok_or(ProgramError::InvalidAccountData)? != 0,
+            claim_expiry: i64::from_le_bytes(read_array(src, 436..444)?),
+            quota_owner: Pubkey::from(quota_owner_bytes),
+            period_seconds: i64::from_le_bytes(read_array(src, 476..484)?),
+            per_period: u64::from_le_bytes(read_array(src, 484..492)?),
+            kyc_verifier: Pubkey::from(kyc_verifier_bytes),
+            lock_after_first_claim: *src.get(524).ok_or(ProgramError::InvalidAccountData)? != 0,
+            launch: Pubkey::from(launch_bytes),
+            launch_duration: i64::from_le_bytes(read_array(src, 557..565)?),
+            auto_claim: *src.get(565).ok_or(ProgramError::InvalidAccountData)? != 0,
+            curve_point_count: *src.get(566).ok_or(ProgramError::InvalidAccountData)?,
+            curve_points,
+            withhold_bps: u16::from_le_bytes(read_array(src, 695..697)?),
+            tax_account: Pubkey::from(tax_account_bytes),
+            allow_early_claim: *src.get(729).ok_or(ProgramError::InvalidAccountData)? != 0,
+            early_claim_penalty_bps: u16::from_le_bytes(read_array(src, 730..732)?),
+            claim_governance: Pubkey::from(claim_governance_bytes),
+            vault: Pubkey::from(vault_bytes),
         })
     }
 
@@ -70,207 +580,6808 @@ impl Pack for VestingState {
         dst[64..72].copy_from_slice(&self.amount.to_le_bytes());
         dst[72..80].copy_from_slice(&self.vesting_start.to_le_bytes());
         dst[80..88].copy_from_slice(&self.vesting_end.to_le_bytes());
+        dst[88] = self.is_initialized as u8;
+        dst[89..97].copy_from_slice(&self.claimed_amount.to_le_bytes());
+        dst[97..99].copy_from_slice(&self.tge_bps.to_le_bytes());
+        dst[99..131].copy_from_slice(self.recovery_authority.as_ref());
+        dst[131..163].copy_from_slice(self.recovery_receiver.as_ref());
+        dst[163..171].copy_from_slice(&self.recovery_requested_at.to_le_bytes());
+        dst[171..179].copy_from_slice(&self.rate_per_second.to_le_bytes());
+        dst[179..187].copy_from_slice(&self.last_claim_at.to_le_bytes());
+        dst[187] = self.decimals;
+        dst[188] = self.vault_authority_bump;
+        dst[189..191].copy_from_slice(&self.claim_fee_bps.to_le_bytes());
+        dst[191..223].copy_from_slice(self.treasury.as_ref());
+        dst[223..255].copy_from_slice(self.mint.as_ref());
+        dst[255..287].copy_from_slice(self.claim_hook.as_ref());
+        dst[287..295].copy_from_slice(&self.checkpoint_at.to_le_bytes());
+        dst[295..303].copy_from_slice(&self.checkpoint_vested.to_le_bytes());
+        dst[303] = self.step_count;
+        dst[304..312].copy_from_slice(&self.claimed_milestones.to_le_bytes());
+        dst[312..320].copy_from_slice(&self.paused_at.to_le_bytes());
+        dst[320..328].copy_from_slice(&self.paused_duration.to_le_bytes());
+        dst[328] = self.is_frozen as u8;
+        dst[329..361].copy_from_slice(self.schedule_hash.as_ref());
+        dst[361..369].copy_from_slice(&self.unit_size.to_le_bytes());
+        dst[369] = self.dual_control as u8;
+        dst[370] = self.irrevocable as u8;
+        dst[371..403].copy_from_slice(self.pending_funder.as_ref());
+        dst[403..435].copy_from_slice(self.refund_owner.as_ref());
+        dst[435] = self.active as u8;
+        dst[436..444].copy_from_slice(&self.claim_expiry.to_le_bytes());
+        dst[444..476].copy_from_slice(self.quota_owner.as_ref());
+        dst[476..484].copy_from_slice(&self.period_seconds.to_le_bytes());
+        dst[484..492].copy_from_slice(&self.per_period.to_le_bytes());
+        dst[492..524].copy_from_slice(self.kyc_verifier.as_ref());
+        dst[524] = self.lock_after_first_claim as u8;
+        dst[525..557].copy_from_slice(self.launch.as_ref());
+        dst[557..565].copy_from_slice(&self.launch_duration.to_le_bytes());
+        dst[565] = self.auto_claim as u8;
+        dst[566] = self.curve_point_count;
+        for (i, (timestamp, cumulative)) in self.curve_points.iter().enumerate() {
+            let offset = 567 + i * 16;
+            dst[offset..offset + 8].copy_from_slice(&timestamp.to_le_bytes());
+            dst[offset + 8..offset + 16].copy_from_slice(&cumulative.to_le_bytes());
+        }
+        dst[695..697].copy_from_slice(&self.withhold_bps.to_le_bytes());
+        dst[697..729].copy_from_slice(self.tax_account.as_ref());
+        dst[729] = self.allow_early_claim as u8;
+        dst[730..732].copy_from_slice(&self.early_claim_penalty_bps.to_le_bytes());
+        dst[732..764].copy_from_slice(self.claim_governance.as_ref());
+        dst[764..796].copy_from_slice(self.vault.as_ref());
     }
 }
 
-entrypoint!(process_instruction);
+impl Sealed for ProgramConfig {}
+impl Pack for ProgramConfig {
+    const LEN: usize = 154;
 
-impl IsInitialized for VestingState {
-    fn is_initialized(&self) -> bool {
-        self.is_initialized
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let treasury_bytes: [u8; 32] = read_array(src, 4..36)?;
+        let kyc_verifier_bytes: [u8; 32] = read_array(src, 112..144)?;
+        let mut allowed_token_programs = [Pubkey::default(); MAX_ALLOWED_TOKEN_PROGRAMS];
+        for (i, token_program) in allowed_token_programs.iter_mut().enumerate() {
+            let offset = 36 + i * 32;
+            let token_program_bytes: [u8; 32] = read_array(src, offset..offset + 32)?;
+            *token_program = Pubkey::from(token_program_bytes);
+        }
+
+        Ok(ProgramConfig {
+            is_initialized: *src.first().ok_or(ProgramError::InvalidAccountData)? != 0,
+            bump: *src.get(1).ok_or(ProgramError::InvalidAccountData)?,
+            claim_fee_bps: u16::from_le_bytes(read_array(src, 2..4)?),
+            treasury: Pubkey::from(treasury_bytes),
+            allowed_token_programs,
+            min_duration: i64::from_le_bytes(read_array(src, 100..108)?),
+            max_schedules_per_funder: u32::from_le_bytes(read_array(src, 108..112)?),
+            kyc_verifier: Pubkey::from(kyc_verifier_bytes),
+            require_ata_vault: *src.get(144).ok_or(ProgramError::InvalidAccountData)? != 0,
+            max_duration: i64::from_le_bytes(read_array(src, 145..153)?),
+            deprecated: *src.get(153).ok_or(ProgramError::InvalidAccountData)? != 0,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1] = self.bump;
+        dst[2..4].copy_from_slice(&self.claim_fee_bps.to_le_bytes());
+        dst[4..36].copy_from_slice(self.treasury.as_ref());
+        for (i, token_program) in self.allowed_token_programs.iter().enumerate() {
+            let offset = 36 + i * 32;
+            dst[offset..offset + 32].copy_from_slice(token_program.as_ref());
+        }
+        dst[100..108].copy_from_slice(&self.min_duration.to_le_bytes());
+        dst[108..112].copy_from_slice(&self.max_schedules_per_funder.to_le_bytes());
+        dst[112..144].copy_from_slice(self.kyc_verifier.as_ref());
+        dst[144] = self.require_ata_vault as u8;
+        dst[145..153].copy_from_slice(&self.max_duration.to_le_bytes());
+        dst[153] = self.deprecated as u8;
+    }
+}
+
+impl Sealed for FunderQuota {}
+
+impl Pack for FunderQuota {
+    const LEN: usize = 6;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(FunderQuota {
+            is_initialized: *src.first().ok_or(ProgramError::InvalidAccountData)? != 0,
+            bump: *src.get(1).ok_or(ProgramError::InvalidAccountData)?,
+            active_schedules: u32::from_le_bytes(read_array(src, 2..6)?),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1] = self.bump;
+        dst[2..6].copy_from_slice(&self.active_schedules.to_le_bytes());
     }
 }
 
+impl Sealed for ClaimLedger {}
+
 /**
- * Initializes a vesting schedule.
- *
- * This function transfers the specified amount of tokens from the funder's account
- * to a vault account and records the vesting details in the vesting state account.
- * The vesting state includes the recipient, funder, amount, vesting start and end times.
- * The vesting start time is set to the current timestamp.
+ * On-chain layout of `ClaimLedger`.
  *
- * Accounts expected by this instruction:
- * 0. `[writable]` The vesting state account to be initialized.
- * 1. `[writable]` The vault account to hold the vested tokens.
- * 2. `[signer]` The funder's account, from which tokens will be transferred.
- * 3. `[]` The recipient's account, which will receive the tokens after vesting.
- * 4. `[]` The SPL token program account.
- * 5. `[]` The Rent sysvar.
- * 6. `[]` The Clock sysvar.
+ * The ledger is a ring buffer of the last `CLAIM_LEDGER_CAPACITY` claims of a schedule.
+ * `account_type` is `CLAIM_LEDGER_ACCOUNT_TYPE` once initialized, `next_index` is the slot the
+ * next claim is written to and `entry_count` the number of slots in use, entries are
+ * `(timestamp, amount)` pairs of 16 bytes each:
  *
- * Parameters:
- * - `amount`: The amount of tokens to be vested.
- * - `vesting_end`: The Unix timestamp when the vesting period ends.
+ * | Offset    | Size | Field           |
+ * |-----------|------|-----------------|
+ * | 0         | 1    | `account_type`  |
+ * | 1         | 1    | `bump`          |
+ * | 2..34     | 32   | `vesting_state` |
+ * | 34..36    | 2    | `next_index`    |
+ * | 36..38    | 2    | `entry_count`   |
+ * | 38..550   | 512  | `entries`       |
  */
-pub fn init_vesting(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    amount: u64,
-    vesting_end: i64,
-) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let vesting_state_info = next_account_info(account_info_iter)?;
-    let vault_info = next_account_info(account_info_iter)?;
-    let funder_info = next_account_info(account_info_iter)?;
-    let recipient_info = next_account_info(account_info_iter)?;
-    let token_program_info = next_account_info(account_info_iter)?;
-    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
-    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+impl Pack for ClaimLedger {
+    const LEN: usize = 38 + CLAIM_LEDGER_CAPACITY * 16;
 
-    if !rent.is_exempt(vesting_state_info.lamports(), vesting_state_info.data_len()) {
-        return Err(ProgramError::AccountNotRentExempt);
-    }
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let vesting_state_bytes: [u8; 32] = read_array(src, 2..34)?;
+        let mut entries = [(0i64, 0u64); CLAIM_LEDGER_CAPACITY];
+        for (i, entry) in entries.iter_mut().enumerate() {
+            let offset = 38 + i * 16;
+            *entry = (
+                i64::from_le_bytes(read_array(src, offset..offset + 8)?),
+                u64::from_le_bytes(read_array(src, offset + 8..offset + 16)?),
+            );
+        }
 
-    let mut vesting_state = VestingState {
-        is_initialized: false,
-        receiver: *recipient_info.key,
-        funder: *funder_info.key,
-        amount,
-        vesting_start: clock.unix_timestamp,
-        vesting_end,
-    };
+        Ok(ClaimLedger {
+            account_type: *src.first().ok_or(ProgramError::InvalidAccountData)?,
+            bump: *src.get(1).ok_or(ProgramError::InvalidAccountData)?,
+            vesting_state: Pubkey::from(vesting_state_bytes),
+            next_index: u16::from_le_bytes(read_array(src, 34..36)?),
+            entry_count: u16::from_le_bytes(read_array(src, 36..38)?),
+            entries,
+        })
+    }
 
-    // Validate if the program has been initialized before
-    if vesting_state_info
-        .try_borrow_data()?
-        .iter()
-        .all(|&byte| byte == 0)
-    {
-        return Err(ProgramError::AccountAlreadyInitialized);
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.account_type;
+        dst[1] = self.bump;
+        dst[2..34].copy_from_slice(self.vesting_state.as_ref());
+        dst[34..36].copy_from_slice(&self.next_index.to_le_bytes());
+        dst[36..38].copy_from_slice(&self.entry_count.to_le_bytes());
+        for (i, (timestamp, amount)) in self.entries.iter().enumerate() {
+            let offset = 38 + i * 16;
+            dst[offset..offset + 8].copy_from_slice(&timestamp.to_le_bytes());
+            dst[offset + 8..offset + 16].copy_from_slice(&amount.to_le_bytes());
+        }
     }
+}
 
-    // Write vesting state to account
-    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+impl Sealed for LaunchState {}
 
-    // Transfer vested tokens to the vault account
-    let transfer_ix = spl_token::instruction::transfer(
-        token_program_info.key,
-        funder_info.key,
-        vault_info.key,
-        funder_info.key,
-        &[],
-        amount,
-    )?;
-    invoke(
-        &transfer_ix,
-        &[
-            funder_info.clone(),
-            vault_info.clone(),
-            token_program_info.clone(),
-        ],
-    )?;
+impl Pack for LaunchState {
+    const LEN: usize = 42;
 
-    Ok(())
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let authority_bytes: [u8; 32] = read_array(src, 2..34)?;
+
+        Ok(LaunchState {
+            is_initialized: *src.first().ok_or(ProgramError::InvalidAccountData)? != 0,
+            bump: *src.get(1).ok_or(ProgramError::InvalidAccountData)?,
+            authority: Pubkey::from(authority_bytes),
+            launch_at: i64::from_le_bytes(read_array(src, 34..42)?),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.is_initialized as u8;
+        dst[1] = self.bump;
+        dst[2..34].copy_from_slice(self.authority.as_ref());
+        dst[34..42].copy_from_slice(&self.launch_at.to_le_bytes());
+    }
 }
 
-/**
- * Claims the vested tokens.
- *
- * This function allows the recipient to claim the vested tokens after the vesting period has ended.
- * It checks the current timestamp to ensure the vesting period is over, then transfers the tokens
- * from the vault account to the recipient's account. The vesting state is marked as uninitialized
- * to prevent further claims.
- *
- * Accounts expected by this instruction:
- * 0. `[writable]` The vesting state account.
- * 1. `[writable]` The vault account holding the vested tokens.
- * 2. `[writable]` The recipient's account, which will receive the tokens.
- * 3. `[]` The SPL token program account.
- * 4. `[]` The Clock sysvar.
- */
-pub fn claim_vesting(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let vesting_state_info = next_account_info(account_info_iter)?;
-    let vault_info = next_account_info(account_info_iter)?;
-    let recipient_info = next_account_info(account_info_iter)?;
-    let token_program_info = next_account_info(account_info_iter)?;
-    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+impl ClaimLedger {
+    /// Appends a claim, evicting the oldest one once the ledger is full.
+    pub fn record(&mut self, timestamp: i64, amount: u64) {
+        let index = self.next_index as usize % CLAIM_LEDGER_CAPACITY;
+        self.entries[index] = (timestamp, amount);
+        self.next_index = ((index + 1) % CLAIM_LEDGER_CAPACITY) as u16;
+        self.entry_count = (self.entry_count + 1).min(CLAIM_LEDGER_CAPACITY as u16);
+    }
+}
 
-    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+entrypoint!(process_instruction);
 
-    // Verify if timestamp is outside vesting period
-    if clock.unix_timestamp <= vesting_state.vesting_end {
-        return Err(ProgramError::Custom(0)); // Vesting period has not ended
+impl IsInitialized for VestingState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
     }
+}
 
-    // Transfer vested tokens to the recipient
-    let transfer_ix = spl_token::instruction::transfer(
-        token_program_info.key,
-        vault_info.key,
-        recipient_info.key,
-        vesting_state_info.key,
-        &[],
-        vesting_state.amount,
-    )?;
-    invoke_signed(
-        &transfer_ix,
-        &[
-            vault_info.clone(),
-            recipient_info.clone(),
-            token_program_info.clone(),
-        ],
-        &[&[b"vesting", &[vesting_state_info.data_len() as u8]]], // Update seeds as needed
-    )?;
+impl IsInitialized for ProgramConfig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
 
-    // Mark the vesting state as not initialized to prevent further claims
-    let mut new_vesting_state = vesting_state;
-    new_vesting_state.is_initialized = false;
-    new_vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+impl IsInitialized for FunderQuota {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
 
-    Ok(())
+impl IsInitialized for ClaimLedger {
+    fn is_initialized(&self) -> bool {
+        self.account_type == CLAIM_LEDGER_ACCOUNT_TYPE
+    }
+}
+
+impl IsInitialized for LaunchState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
 }
 
 /**
- * Processes instructions for the smart contract.
+ * Derives the program config PDA.
  *
- * This function is the main entry point for the program. It dispatches calls to the appropriate
- * function based on the instruction data. The first byte of the instruction data specifies the
- * instruction to be executed.
+ * Parameters:
+ * - `program_id`: The program ID.
+ *
+ * Returns:
+ * - The program config address and its bump seed.
+ */
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+/**
+ * Reads the program config, validating that the account is the program config PDA.
  *
  * Parameters:
- * - `_program_id`: The program ID.
- * - `_accounts`: The accounts required for the instruction.
- * - `_instruction_data`: The instruction data.
+ * - `program_id`: The program ID.
+ * - `config_info`: The program config account.
  *
- * Supported instructions:
- * - `0`: Initialize vesting (calls `init_vesting`).
- * - `1`: Claim vesting (calls `claim_vesting`).
+ * Returns:
+ * - The initialized program config.
  */
-pub fn process_instruction(
+fn load_config(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+) -> Result<ProgramConfig, ProgramError> {
+    if config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let config = ProgramConfig::unpack(&config_info.try_borrow_data()?)?;
+    let config_address =
+        Pubkey::create_program_address(&[CONFIG_SEED, &[config.bump]], program_id)?;
+    if *config_info.key != config_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(config)
+}
+
+/**
+ * Derives the funder quota PDA of a funder.
+ *
+ * Parameters:
+ * - `funder`: The funder address.
+ * - `program_id`: The program ID.
+ *
+ * Returns:
+ * - The funder quota address and its bump seed.
+ */
+pub fn find_funder_quota_address(funder: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FUNDER_QUOTA_SEED, funder.as_ref()], program_id)
+}
+
+/**
+ * Reads a funder quota, validating that the account is the funder quota PDA of the funder.
+ *
+ * Parameters:
+ * - `program_id`: The program ID.
+ * - `quota_info`: The funder quota account.
+ * - `funder`: The funder the quota is expected to belong to.
+ *
+ * Returns:
+ * - The initialized funder quota.
+ */
+fn load_funder_quota(
+    program_id: &Pubkey,
+    quota_info: &AccountInfo,
+    funder: &Pubkey,
+) -> Result<FunderQuota, ProgramError> {
+    if quota_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let quota = FunderQuota::unpack(&quota_info.try_borrow_data()?)?;
+    let quota_address = Pubkey::create_program_address(
+        &[FUNDER_QUOTA_SEED, funder.as_ref(), &[quota.bump]],
+        program_id,
+    )?;
+    if *quota_info.key != quota_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(quota)
+}
+
+/**
+ * Releases the funder quota slot held by a schedule that is being closed.
+ *
+ * Schedules created without a per-funder cap hold no slot and need no quota account.
+ *
+ * Parameters:
+ * - `program_id`: The program ID.
+ * - `quota_info`: The funder quota account of `quota_owner`, if the schedule holds a slot.
+ * - `vesting_state`: The schedule being closed.
+ */
+fn release_funder_quota(
+    program_id: &Pubkey,
+    quota_info: Option<&AccountInfo>,
+    vesting_state: &VestingState,
+) -> ProgramResult {
+    if vesting_state.quota_owner == Pubkey::default() {
+        return Ok(());
+    }
+    let quota_info = quota_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let mut quota = load_funder_quota(program_id, quota_info, &vesting_state.quota_owner)?;
+    quota.active_schedules = quota
+        .active_schedules
+        .checked_sub(1)
+        .ok_or(VestingError::MathOverflow)?;
+    quota.pack_into_slice(&mut quota_info.try_borrow_mut_data()?);
+    Ok(())
+}
+
+/**
+ * Derives the claim ledger PDA of a schedule.
+ *
+ * Parameters:
+ * - `vesting_state`: The vesting state account address.
+ * - `program_id`: The program ID.
+ *
+ * Returns:
+ * - The claim ledger address and its bump seed.
+ */
+pub fn find_claim_ledger_address(vesting_state: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CLAIM_LEDGER_SEED, vesting_state.as_ref()], program_id)
+}
+
+/**
+ * Reads a claim ledger, validating that the account is the claim ledger PDA of the schedule.
+ *
+ * Parameters:
+ * - `program_id`: The program ID.
+ * - `ledger_info`: The claim ledger account.
+ * - `vesting_state`: The vesting state account the ledger is expected to belong to.
+ *
+ * Returns:
+ * - The initialized claim ledger.
+ */
+fn load_claim_ledger(
+    program_id: &Pubkey,
+    ledger_info: &AccountInfo,
+    vesting_state: &Pubkey,
+) -> Result<ClaimLedger, ProgramError> {
+    if ledger_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let ledger = ClaimLedger::unpack(&ledger_info.try_borrow_data()?)?;
+    let ledger_address = Pubkey::create_program_address(
+        &[CLAIM_LEDGER_SEED, vesting_state.as_ref(), &[ledger.bump]],
+        program_id,
+    )?;
+    if *ledger_info.key != ledger_address || ledger.vesting_state != *vesting_state {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(ledger)
+}
+
+/**
+ * Derives the launch PDA of a launch authority.
+ *
+ * Parameters:
+ * - `authority`: The launch authority address.
+ * - `program_id`: The program ID.
+ *
+ * Returns:
+ * - The launch address and its bump seed.
+ */
+pub fn find_launch_address(authority: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LAUNCH_SEED, authority.as_ref()], program_id)
+}
+
+/**
+ * Reads a launch, validating that the account is the launch PDA of its authority.
+ *
+ * Parameters:
+ * - `program_id`: The program ID.
+ * - `launch_info`: The launch account.
+ *
+ * Returns:
+ * - The initialized launch.
+ */
+fn load_launch(
+    program_id: &Pubkey,
+    launch_info: &AccountInfo,
+) -> Result<LaunchState, ProgramError> {
+    if launch_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let launch = LaunchState::unpack(&launch_info.try_borrow_data()?)?;
+    let launch_address = Pubkey::create_program_address(
+        &[LAUNCH_SEED, launch.authority.as_ref(), &[launch.bump]],
+        program_id,
+    )?;
+    if *launch_info.key != launch_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(launch)
+}
+
+/**
+ * Validates that a signer is the upgrade authority of the program.
+ *
+ * The upgrade authority is read from the program data account of the upgradeable BPF loader,
+ * laid out as a `u32` variant tag (3, `ProgramData`), the `u64` deployment slot and an optional
+ * upgrade authority (1 byte tag + 32 bytes). Immutable programs have no upgrade authority, so
+ * their config cannot be changed.
+ *
+ * Parameters:
+ * - `program_id`: The program ID.
+ * - `authority_info`: The account expected to be the signing upgrade authority.
+ * - `program_data_info`: The program data account of the program.
+ */
+fn check_upgrade_authority(
+    program_id: &Pubkey,
+    authority_info: &AccountInfo,
+    program_data_info: &AccountInfo,
+) -> ProgramResult {
+    let (program_data_address, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if *program_data_info.key != program_data_address
+        || *program_data_info.owner != bpf_loader_upgradeable::id()
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let program_data = program_data_info.try_borrow_data()?;
+    if program_data.len() < 45 || program_data[0..4] != 3u32.to_le_bytes() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let upgrade_authority_bytes: [u8; 32] = program_data[13..45].try_into().unwrap();
+    if program_data[12] != 1
+        || !authority_info.is_signer
+        || *authority_info.key != Pubkey::from(upgrade_authority_bytes)
+    {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/**
+ * Validates the protocol-wide parameters of the program config.
+ *
+ * Parameters:
+ * - `params`: The config parameters.
+ */
+fn validate_config_params(params: &ConfigParams) -> ProgramResult {
+    // Validate the claim fee does not exceed 100% and has somewhere to go
+    if params.claim_fee_bps as u64 > BPS_DENOMINATOR
+        || (params.claim_fee_bps > 0 && params.treasury == Pubkey::default())
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if params.min_duration < 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    // Validate a maximum duration leaves room for the minimum one
+    if params.max_duration < 0
+        || (params.max_duration > 0 && params.max_duration < params.min_duration)
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/**
+ * Derives the vault authority PDA of a vesting state account.
+ *
+ * The vault authority owns the vault token account and signs every transfer out of it, keeping
+ * token authority separate from the account storing the vesting data.
+ *
+ * Parameters:
+ * - `vesting_state`: The vesting state account address.
+ * - `program_id`: The program ID.
+ *
+ * Returns:
+ * - The vault authority address and its bump seed.
+ */
+pub fn find_vault_authority(vesting_state: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_AUTHORITY_SEED, vesting_state.as_ref()], program_id)
+}
+
+/**
+ * Derives the associated token account of a wallet for a mint.
+ *
+ * Parameters:
+ * - `wallet`: The owner of the token account.
+ * - `mint`: The token mint.
+ * - `token_program`: The SPL token program the mint belongs to.
+ *
+ * Returns:
+ * - The associated token account address and its bump seed under the associated token account
+ *   program.
+ */
+pub fn find_associated_token_address(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+}
+
+/**
+ * Derives the canonical vault of a vesting state account.
+ *
+ * Any token account owned by the vault authority PDA is accepted as the vault unless the program
+ * config sets `require_ata_vault`, the associated token account of the vault authority for the
+ * mint is the deterministic choice for clients. It can be created by anyone through the
+ * associated token account program before init.
+ *
+ * Parameters:
+ * - `vesting_state`: The vesting state account address.
+ * - `mint`: The token mint of the schedule.
+ * - `token_program`: The SPL token program the mint belongs to.
+ * - `program_id`: The program ID.
+ *
+ * Returns:
+ * - The vault address and its bump seed under the associated token account program.
+ */
+pub fn vault_address(
+    vesting_state: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    let (vault_authority, _) = find_vault_authority(vesting_state, program_id);
+    find_associated_token_address(&vault_authority, mint, token_program)
+}
+
+/**
+ * Builds the signer seeds of the vault authority PDA.
+ *
+ * Parameters:
+ * - `vesting_state`: The vesting state account address.
+ * - `bump`: The vault authority bump seed stored in the vesting state.
+ */
+fn vault_authority_seeds<'a>(vesting_state: &'a Pubkey, bump: &'a [u8; 1]) -> [&'a [u8]; 3] {
+    [VAULT_AUTHORITY_SEED, vesting_state.as_ref(), bump]
+}
+
+/**
+ * Validates that an account is the vault authority PDA of a vesting state account.
+ *
+ * Parameters:
+ * - `vault_authority_info`: The account passed as vault authority.
+ * - `vesting_state_info`: The vesting state account.
+ * - `vesting_state`: The unpacked vesting state, holding the bump seed.
+ */
+fn check_vault_authority(
+    program_id: &Pubkey,
+    vault_authority_info: &AccountInfo,
+    vesting_state_info: &AccountInfo,
+    vesting_state: &VestingState,
+) -> ProgramResult {
+    let bump = [vesting_state.vault_authority_bump];
+    let vault_authority = Pubkey::create_program_address(
+        &vault_authority_seeds(vesting_state_info.key, &bump),
+        program_id,
+    )?;
+    if *vault_authority_info.key != vault_authority {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+/**
+ * Computes the hash of the canonical parameters of a schedule.
+ *
+ * The hash is stored in `schedule_hash` when a schedule is created, so clients can verify the terms
+ * it was created with. It covers, in this order: `receiver`, `funder`, `mint`, `amount`,
+ * `vesting_start`, `vesting_end`, `tge_bps`, `rate_per_second`, `step_count`, `recovery_authority`,
+ * `claim_fee_bps`, `treasury`, `claim_hook`, `unit_size`, `dual_control`, `irrevocable`,
+ * `claim_expiry`, `period_seconds`, `per_period`, `kyc_verifier`, `lock_after_first_claim`,
+ * `launch`, `launch_duration`, `auto_claim`, `curve_point_count`, the curve points in use,
+ * `withhold_bps`, `tax_account`, `allow_early_claim`, `early_claim_penalty_bps` and
+ * `claim_governance`, with integers encoded little-endian as in the account layout.
+ * The timeline of a schedule created ahead of its launch is hashed as pending, see
+ * `is_launch_pending`.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ *
+ * Returns:
+ * - The SHA-256 hash of the schedule parameters.
+ */
+pub fn schedule_hash(state: &VestingState) -> Hash {
+    let curve_bytes: Vec<u8> = curve_points(state)
+        .iter()
+        .flat_map(|(timestamp, cumulative)| {
+            timestamp
+                .to_le_bytes()
+                .into_iter()
+                .chain(cumulative.to_le_bytes())
+        })
+        .collect();
+    hashv(&[
+        state.receiver.as_ref(),
+        state.funder.as_ref(),
+        state.mint.as_ref(),
+        &state.amount.to_le_bytes(),
+        &state.vesting_start.to_le_bytes(),
+        &state.vesting_end.to_le_bytes(),
+        &state.tge_bps.to_le_bytes(),
+        &state.rate_per_second.to_le_bytes(),
+        &[state.step_count],
+        state.recovery_authority.as_ref(),
+        &state.claim_fee_bps.to_le_bytes(),
+        state.treasury.as_ref(),
+        state.claim_hook.as_ref(),
+        &state.unit_size.to_le_bytes(),
+        &[state.dual_control as u8],
+        &[state.irrevocable as u8],
+        &state.claim_expiry.to_le_bytes(),
+        &state.period_seconds.to_le_bytes(),
+        &state.per_period.to_le_bytes(),
+        state.kyc_verifier.as_ref(),
+        &[state.lock_after_first_claim as u8],
+        state.launch.as_ref(),
+        &state.launch_duration.to_le_bytes(),
+        &[state.auto_claim as u8],
+        &[state.curve_point_count],
+        &curve_bytes,
+        &state.withhold_bps.to_le_bytes(),
+        state.tax_account.as_ref(),
+        &[state.allow_early_claim as u8],
+        &state.early_claim_penalty_bps.to_le_bytes(),
+        state.claim_governance.as_ref(),
+    ])
+}
+
+/**
+ * Validates the KYC attestation of a schedule's recipient.
+ *
+ * Attestations are accounts owned by the KYC verifier snapshotted into the schedule, laid out as
+ * the attested address (32 bytes) followed by the Unix timestamp the attestation expires at
+ * (8 bytes, little-endian). Any data after that is left to the verifier.
+ *
+ * Parameters:
+ * - `attestation_info`: The KYC attestation account.
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ */
+fn check_kyc_attestation(
+    attestation_info: &AccountInfo,
+    state: &VestingState,
+    now: i64,
+) -> ProgramResult {
+    if *attestation_info.owner != state.kyc_verifier {
+        return Err(VestingError::InvalidKycAttestation.into());
+    }
+    let data = attestation_info.try_borrow_data()?;
+    let attested: [u8; 32] =
+        read_array(&data, 0..32).map_err(|_| VestingError::InvalidKycAttestation)?;
+    let expires_at = i64::from_le_bytes(
+        read_array(&data, 32..40).map_err(|_| VestingError::InvalidKycAttestation)?,
+    );
+    if Pubkey::from(attested) != state.receiver {
+        return Err(VestingError::InvalidKycAttestation.into());
+    }
+    if now >= expires_at {
+        return Err(VestingError::KycAttestationExpired.into());
+    }
+    Ok(())
+}
+
+/**
+ * Validates that a claim is invoked by the governance program of a schedule.
+ *
+ * Governance programs authorize claims by invoking them through CPI, e.g. when a DAO executes a
+ * passed proposal. Only the program of the top-level instruction can be introspected, so the
+ * governance program has to be the program the transaction calls, and a claim sent directly or
+ * through any other program is rejected.
+ *
+ * Parameters:
+ * - `instructions_info`: The Instructions sysvar.
+ * - `state`: The vesting state.
+ */
+fn check_claim_governance(instructions_info: &AccountInfo, state: &VestingState) -> ProgramResult {
+    if get_stack_height() <= TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Err(VestingError::GovernanceRequired.into());
+    }
+    let current_index = load_current_index_checked(instructions_info)?;
+    let current_instruction =
+        load_instruction_at_checked(current_index as usize, instructions_info)?;
+    if current_instruction.program_id != state.claim_governance {
+        return Err(VestingError::GovernanceRequired.into());
+    }
+    Ok(())
+}
+
+/**
+ * Validates that enough signers of an SPL token multisig funder signed.
+ *
+ * The token program validates the signers of a multisig funder on the transfer out of its token
+ * account, but that transfer is skipped when co-funders cover the whole amount. The signers are
+ * therefore validated here as well, so a schedule can never be attributed to a multisig that did
+ * not authorize it.
+ *
+ * Parameters:
+ * - `funder_info`: The funder, an SPL token multisig.
+ * - `token_program_info`: The SPL token program owning the multisig.
+ * - `signer_infos`: The signers passed for the multisig.
+ */
+fn check_multisig_signers(
+    funder_info: &AccountInfo,
+    token_program_info: &AccountInfo,
+    signer_infos: &[AccountInfo],
+) -> ProgramResult {
+    if funder_info.owner != token_program_info.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let multisig = spl_token::state::Multisig::unpack(&funder_info.try_borrow_data()?)?;
+    let members = &multisig.signers[..multisig.n as usize];
+    let mut matched = [false; spl_token::instruction::MAX_SIGNERS];
+    let mut signed = 0u8;
+    for signer_info in signer_infos {
+        if !signer_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let position = members
+            .iter()
+            .position(|member| member == signer_info.key)
+            .ok_or(ProgramError::MissingRequiredSignature)?;
+        if !matched[position] {
+            matched[position] = true;
+            signed += 1;
+        }
+    }
+    if signed < multisig.m {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/**
+ * Validates that the accounts passed for distinct roles have distinct keys.
+ *
+ * Passing e.g. the vault also as the destination of a transfer out of it would turn the
+ * transfer into a confusing self-transfer.
+ *
+ * Parameters:
+ * - `accounts`: The accounts that must all differ.
+ */
+fn check_distinct_accounts(accounts: &[&AccountInfo]) -> VestingResult<()> {
+    for (i, account) in accounts.iter().enumerate() {
+        if accounts[i + 1..]
+            .iter()
+            .any(|other| other.key == account.key)
+        {
+            return Err(VestingError::DuplicateAccount);
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Converts a timestamp to the vesting clock of a schedule.
+ *
+ * The vesting clock stands still while the schedule is paused and runs behind the wall clock
+ * by the accumulated `paused_duration` afterwards, so every pause shifts the effective end of
+ * the schedule by its length. All schedule timestamps (`vesting_start`, `vesting_end`,
+ * `checkpoint_at`) are on the vesting clock. As the curve is evaluated on the vesting clock,
+ * nothing accrues while paused and a resumed schedule vests at exactly its pre-pause rate.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - The vesting clock time at `now`.
+ */
+pub fn vesting_clock(state: &VestingState, now: i64) -> i64 {
+    let now = if state.paused_at != 0 {
+        now.min(state.paused_at)
+    } else {
+        now
+    };
+    now.saturating_sub(state.paused_duration)
+}
+
+/**
+ * Computes when a schedule is fully vested, including the time it has been paused so far.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - The wall clock time of `vesting_end` if the schedule were resumed at `now`.
+ */
+pub fn effective_vesting_end(state: &VestingState, now: i64) -> i64 {
+    state
+        .vesting_end
+        .saturating_add(now.saturating_sub(vesting_clock(state, now)))
+}
+
+/**
+ * Computes when a schedule that has not started yet starts vesting.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - The wall clock time of `vesting_start` if the schedule were resumed at `now`, or `None` once
+ *   the schedule has started.
+ */
+pub fn starts_at(state: &VestingState, now: i64) -> Option<i64> {
+    if vesting_clock(state, now) >= state.vesting_start {
+        return None;
+    }
+    Some(
+        state
+            .vesting_start
+            .saturating_add(now.saturating_sub(vesting_clock(state, now))),
+    )
+}
+
+/**
+ * Computes the total amount vested at the given timestamp.
+ *
+ * The TGE portion (`tge_bps` of `amount`) is unlocked as soon as vesting starts, the
+ * remainder streams linearly from `vesting_start` to `vesting_end`. After a partial revoke the
+ * curve restarts from the last checkpoint, streaming what is left of the reduced amount
+ * from `checkpoint_at` to `vesting_end`. Stepped schedules (`step_count > 0`) release the
+ * remainder in `step_count` equal milestones instead, see `milestone_unlock_at`. Drip schedules
+ * (`rate_per_second > 0`) instead release `rate_per_second` tokens per second since the last
+ * claim until the vault is drained, and periodic schedules (`period_seconds > 0`) release
+ * `per_period` tokens at every full `period_seconds` since `vesting_start`. Curve schedules
+ * (`curve_point_count > 0`) interpolate linearly between their control points, see
+ * `curve_vested_amount`.
+ *
+ * The end is inclusive: at `now >= vesting_end` the full amount is vested, matching the
+ * linear math at `elapsed == duration`, while at `vesting_end - 1` the last second is still
+ * streaming. Before `vesting_start` nothing is vested, not even the TGE portion, so a clock
+ * behind the start never yields a negative elapsed time. Paused time does not count towards
+ * vesting, see `vesting_clock`.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - The cumulative amount vested, including already claimed tokens.
+ */
+pub fn vested_amount(state: &VestingState, now: i64) -> u64 {
+    let now = vesting_clock(state, now);
+    if now < state.vesting_start {
+        return 0;
+    }
+
+    // Drip schedules have no fixed end, they release at a fixed rate until drained
+    if state.rate_per_second > 0 {
+        let elapsed = now.saturating_sub(state.last_claim_at).max(0) as u128;
+        let remaining = state.amount - state.claimed_amount;
+        let dripped = (state.rate_per_second as u128 * elapsed).min(remaining as u128) as u64;
+        return state.claimed_amount + dripped;
+    }
+
+    // Periodic schedules unlock a fixed amount at every full period since the start
+    if state.period_seconds > 0 {
+        let periods_elapsed = ((now - state.vesting_start) / state.period_seconds) as u128;
+        return (periods_elapsed * state.per_period as u128).min(state.amount as u128) as u64;
+    }
+
+    // Fully vested (inclusive of `vesting_end`), no need for the fractional math
+    if now >= state.vesting_end {
+        return state.amount;
+    }
+
+    if state.curve_point_count > 0 {
+        return curve_vested_amount(state, now);
+    }
+
+    if state.step_count > 0 {
+        let passed = passed_milestones(state, now) as u128;
+        let streamed = state.amount - tge_portion(state);
+        return tge_portion(state) + (streamed as u128 * passed / state.step_count as u128) as u64;
+    }
+
+    let streamed = state.amount - state.checkpoint_vested;
+
+    let duration = state.vesting_end - state.checkpoint_at;
+    // Clamp in signed arithmetic before the u128 cast, a negative elapsed would wrap around
+    let elapsed = now.saturating_sub(state.checkpoint_at).max(0);
+
+    let streamed_vested = (streamed as u128 * elapsed as u128 / duration as u128) as u64;
+    state.checkpoint_vested + streamed_vested
+}
+
+/**
+ * Returns the control points in use of a curve schedule.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ *
+ * Returns:
+ * - The first `curve_point_count` curve points, empty for schedules that are not curves.
+ */
+pub fn curve_points(state: &VestingState) -> &[(i64, u64)] {
+    &state.curve_points[..(state.curve_point_count as usize).min(MAX_CURVE_POINTS)]
+}
+
+/**
+ * Computes the amount vested by a curve schedule at the given timestamp.
+ *
+ * The curve is piecewise-linear through its `(timestamp, cumulative_amount)` control points: at
+ * each point exactly its cumulative amount is vested, and in between the amount grows linearly
+ * from one point to the next, computed in `u128`. A segment that keeps the cumulative amount
+ * flat is a cliff, a steep segment of a second releases a lump sum, so cliff, linear and stepped
+ * schedules are all special cases. The first point is `vesting_start` and the last one, at the
+ * full `amount`, is `vesting_end`.
+ *
+ * Parameters:
+ * - `state`: The vesting state, which must be a curve schedule.
+ * - `now`: The timestamp on the vesting clock, see `vesting_clock`, from `vesting_start` to
+ *   `vesting_end`.
+ *
+ * Returns:
+ * - The cumulative amount vested, including already claimed tokens.
+ */
+fn curve_vested_amount(state: &VestingState, now: i64) -> u64 {
+    for segment in curve_points(state).windows(2) {
+        let ((from_at, from_vested), (to_at, to_vested)) = (segment[0], segment[1]);
+        if now < to_at {
+            let elapsed = now.saturating_sub(from_at).max(0) as u128;
+            let duration = (to_at - from_at) as u128;
+            let segment_vested = (to_vested - from_vested) as u128 * elapsed / duration;
+            return from_vested + segment_vested as u64;
+        }
+    }
+    state.amount
+}
+
+/**
+ * Computes the fraction of a schedule vested at the given timestamp, in basis points.
+ *
+ * This helper is meant for progress bars, it follows `vested_amount` and rounds down so a
+ * schedule only reports `BPS_DENOMINATOR` once it is fully vested.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - The vested fraction between `0` and `BPS_DENOMINATOR`, or `0` for an empty schedule.
+ */
+pub fn vested_bps(state: &VestingState, now: i64) -> u16 {
+    if state.amount == 0 {
+        return 0;
+    }
+    let vested = vested_amount(state, now).min(state.amount) as u128;
+    (vested * BPS_DENOMINATOR as u128 / state.amount as u128) as u16
+}
+
+/**
+ * Moves the checkpoint of a linear schedule to the given timestamp.
+ *
+ * This helper records what has vested so far in `checkpoint_vested`, so that changes to
+ * `amount` only affect the part of the schedule that is still streaming. Drip, stepped,
+ * periodic and curve schedules have no linear stream to checkpoint and are left untouched.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ */
+fn checkpoint(state: &mut VestingState, now: i64) {
+    let vesting_now = vesting_clock(state, now);
+    if state.rate_per_second > 0
+        || state.step_count > 0
+        || state.period_seconds > 0
+        || state.curve_point_count > 0
+        || vesting_now <= state.checkpoint_at
+    {
+        return;
+    }
+    state.checkpoint_vested = vested_amount(state, now);
+    state.checkpoint_at = vesting_now.min(state.vesting_end);
+}
+
+/**
+ * Computes the TGE portion of a schedule, `tge_bps` of `amount`.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ */
+fn tge_portion(state: &VestingState) -> u64 {
+    (state.amount as u128 * state.tge_bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+/**
+ * Counts the milestones of a stepped schedule reached at the given timestamp.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The timestamp on the vesting clock, see `vesting_clock`.
+ *
+ * Returns:
+ * - The number of reached milestones, at most `step_count`.
+ */
+fn passed_milestones(state: &VestingState, now: i64) -> u8 {
+    if now < state.vesting_start {
+        return 0;
+    }
+    let duration = (state.vesting_end - state.vesting_start) as i128;
+    let elapsed = (now - state.vesting_start) as i128;
+    (elapsed * state.step_count as i128 / duration).min(state.step_count as i128) as u8
+}
+
+/**
+ * Computes when a milestone of a stepped schedule unlocks.
+ *
+ * Milestones are spread evenly over the schedule, milestone `index` (0-based) unlocks once
+ * `(index + 1) / step_count` of the duration has elapsed, so the last one unlocks at
+ * `vesting_end`.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `index`: The milestone index.
+ *
+ * Returns:
+ * - The timestamp on the vesting clock at which the milestone unlocks, see `vesting_clock`.
+ */
+pub fn milestone_unlock_at(state: &VestingState, index: u8) -> i64 {
+    let duration = (state.vesting_end - state.vesting_start) as i128;
+    let steps = state.step_count as i128;
+    // Round up so the milestone is reached exactly when `passed_milestones` counts it
+    let offset = (duration * (index as i128 + 1) + steps - 1) / steps;
+    state.vesting_start + offset as i64
+}
+
+/**
+ * Computes the tokens released by a single milestone of a stepped schedule.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `index`: The milestone index.
+ *
+ * Returns:
+ * - The milestone's share of the non-TGE amount, with rounding dust going to later milestones.
+ */
+pub fn milestone_amount(state: &VestingState, index: u8) -> u64 {
+    let streamed = (state.amount - tge_portion(state)) as u128;
+    let steps = state.step_count as u128;
+    let vested_after = streamed * (index as u128 + 1) / steps;
+    let vested_before = streamed * index as u128 / steps;
+    (vested_after - vested_before) as u64
+}
+
+/**
+ * Computes the largest residual vault balance swept as dust when a schedule is closed out.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ *
+ * Returns:
+ * - `VAULT_DUST_BPS` of `amount`.
+ */
+pub fn vault_dust_threshold(state: &VestingState) -> u64 {
+    (state.amount as u128 * VAULT_DUST_BPS as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+/**
+ * Resolves the owner of the token account receiving revoked tokens.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ *
+ * Returns:
+ * - `refund_owner`, or the funder if no refund owner is recorded.
+ */
+pub fn revoke_refund_owner(state: &VestingState) -> Pubkey {
+    if state.refund_owner == Pubkey::default() {
+        state.funder
+    } else {
+        state.refund_owner
+    }
+}
+
+/**
+ * Tests whether a schedule is still waiting for the launch it vests from.
+ *
+ * Schedules created with a launch keep `vesting_start` and `vesting_end` at `i64::MAX`, so
+ * nothing vests, until `apply_launch` sets them from the launch timestamp and
+ * `launch_duration`.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ */
+pub fn is_launch_pending(state: &VestingState) -> bool {
+    state.launch != Pubkey::default() && state.vesting_start == i64::MAX
+}
+
+/**
+ * Tests whether the recipient of a schedule can no longer be redirected.
+ *
+ * Schedules created with `lock_after_first_claim` lock their recipient once anything has been
+ * claimed, so a pending or future recovery cannot redirect the remaining tokens.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ */
+pub fn is_recipient_locked(state: &VestingState) -> bool {
+    state.lock_after_first_claim && state.claimed_amount > 0
+}
+
+/**
+ * Tests whether a schedule has been closed out.
+ *
+ * A schedule is closed out once nothing is left for the recipient, after its final claim, a
+ * revoke of its whole unvested remainder, a reclaim after its claim window expired or the
+ * recipient relinquishing it. Its
+ * vesting state is kept, marked as not initialized with `claimed_amount == amount`, until the
+ * funder closes it with `close_schedule`.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ */
+pub fn is_closed_out(state: &VestingState) -> bool {
+    !state.is_initialized
+        && state.funder != Pubkey::default()
+        && state.claimed_amount == state.amount
+}
+
+/**
+ * Tests whether the claim window of a schedule has expired.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - Whether the schedule has a `claim_expiry` and `now` is at or after it.
+ */
+pub fn is_claim_expired(state: &VestingState, now: i64) -> bool {
+    state.claim_expiry != 0 && now >= state.claim_expiry
+}
+
+/**
+ * Builds the bitmap of the first `count` milestones.
+ *
+ * Parameters:
+ * - `count`: The number of milestones, at most `MAX_MILESTONES`.
+ */
+fn milestone_mask(count: u8) -> u64 {
+    if count >= MAX_MILESTONES {
+        u64::MAX
+    } else {
+        (1u64 << count) - 1
+    }
+}
+
+/**
+ * Builds the bitmap of a single milestone.
+ *
+ * Parameters:
+ * - `index`: The milestone index, below `MAX_MILESTONES`.
+ */
+fn milestone_bit(index: u8) -> u64 {
+    1u64 << index
+}
+
+/**
+ * Tests whether a milestone of a stepped schedule has been claimed.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `index`: The milestone index, below `MAX_MILESTONES`.
+ */
+pub fn is_milestone_claimed(state: &VestingState, index: u8) -> bool {
+    state.claimed_milestones & milestone_bit(index) != 0
+}
+
+/**
+ * Records milestones of a stepped schedule as claimed in the `claimed_milestones` bitmap.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `milestones`: The bitmap of the claimed milestones, see `milestone_bit` and
+ *   `milestone_mask`.
+ */
+fn set_milestones_claimed(state: &mut VestingState, milestones: u64) {
+    state.claimed_milestones |= milestones;
+}
+
+/**
+ * Rounds a vested amount down to a whole number of units for unit-aligned schedules.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `vested`: The cumulative vested amount.
+ *
+ * Returns:
+ * - `vested` rounded down to a multiple of `unit_size`, or unchanged once fully vested or if
+ *   the schedule has no `unit_size`.
+ */
+fn align_to_unit(state: &VestingState, vested: u64) -> u64 {
+    if state.unit_size == 0 || vested >= state.amount {
+        return vested;
+    }
+    vested - vested % state.unit_size
+}
+
+/**
+ * Computes the amount the recipient can claim at the given timestamp.
+ *
+ * Unit-aligned schedules (`unit_size > 0`) only release whole units, so every intermediate
+ * claim leaves `claimed_amount` a multiple of `unit_size` and the final claim drains the
+ * remainder.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - The vested amount minus what has already been claimed.
+ */
+pub fn claimable_amount(state: &VestingState, now: i64) -> u64 {
+    // Common end-of-schedule claim, everything left is claimable
+    let vesting_now = vesting_clock(state, now);
+    if vesting_now >= state.vesting_end && vesting_now >= state.vesting_start {
+        return state.amount.saturating_sub(state.claimed_amount);
+    }
+    align_to_unit(state, vested_amount(state, now)).saturating_sub(state.claimed_amount)
+}
+
+/**
+ * Computes the claimable amount of a vesting state account from its raw data.
+ *
+ * This helper lets off-chain clients get the same number the program computes on-chain
+ * without a running validator, e.g. from account data fetched over RPC. Fully claimed
+ * schedules report 0, matching `read_claimable_amounts`.
+ *
+ * Parameters:
+ * - `account_data`: The data of the vesting state account.
+ * - `now`: The Unix timestamp to evaluate the schedule at.
+ *
+ * Returns:
+ * - The amount the recipient can claim at `now`.
+ */
+pub fn simulate_claimable(account_data: &[u8], now: i64) -> Result<u64, ProgramError> {
+    if account_data.len() < VestingState::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let state = VestingState::unpack_from_slice(&account_data[..VestingState::LEN])?;
+    if !state.is_initialized {
+        return Ok(0);
+    }
+    Ok(claimable_amount(&state, now))
+}
+
+/**
+ * Samples the cumulative vested amount of a schedule at evenly spaced timestamps.
+ *
+ * The first sample is taken at `start` and the last at `end`, with the timestamps in between
+ * rounded down. A single sample is taken at `start`.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `start`: The Unix timestamp of the first sample.
+ * - `end`: The Unix timestamp of the last sample, at or after `start`.
+ * - `sample_count`: The number of samples, at least 1.
+ *
+ * Returns:
+ * - The `(timestamp, cumulative_vested)` samples in timestamp order.
+ */
+pub fn sample_vesting_curve(
+    state: &VestingState,
+    start: i64,
+    end: i64,
+    sample_count: u8,
+) -> Vec<(i64, u64)> {
+    let span = (end as i128 - start as i128).max(0);
+    let intervals = (sample_count as i128 - 1).max(1);
+    (0..sample_count as i128)
+        .map(|i| {
+            let timestamp = (start as i128 + span * i / intervals) as i64;
+            (timestamp, vested_amount(state, timestamp))
+        })
+        .collect()
+}
+
+/**
+ * Lists the milestones of a stepped schedule with their unlock times.
+ *
+ * Unlock times are wall clock timestamps as if the schedule were resumed at `now`, so they move
+ * out with every second a paused schedule stays paused, see `effective_vesting_end`.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ * - `first`: The index of the first milestone to list.
+ * - `count`: The maximum number of milestones to list.
+ *
+ * Returns:
+ * - The `(index, unlock_at, amount, claimed)` milestones from `first` in index order, empty for
+ *   schedules that are not stepped.
+ */
+pub fn list_milestones(
+    state: &VestingState,
+    now: i64,
+    first: u8,
+    count: usize,
+) -> Vec<(u8, i64, u64, bool)> {
+    let paused_offset = now.saturating_sub(vesting_clock(state, now));
+    (first..state.step_count)
+        .take(count)
+        .map(|index| {
+            (
+                index,
+                milestone_unlock_at(state, index).saturating_add(paused_offset),
+                milestone_amount(state, index),
+                is_milestone_claimed(state, index),
+            )
+        })
+        .collect()
+}
+
+/**
+ * Computes the protocol fee taken from a claim.
+ *
+ * The fee is computed in `u128`, so it cannot overflow for any amount. A fee above
+ * `BPS_DENOMINATOR`, which the program config rejects, would exceed the amount and fails with
+ * `MathOverflow` instead of being truncated.
+ *
+ * Parameters:
+ * - `amount`: The claimed amount.
+ * - `fee_bps`: The claim fee in basis points.
+ *
+ * Returns:
+ * - The share of `amount` routed to the treasury.
+ */
+pub fn claim_fee(amount: u64, fee_bps: u16) -> VestingResult<u64> {
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(VestingError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    u64::try_from(fee)
+        .ok()
+        .filter(|&fee| fee <= amount)
+        .ok_or(VestingError::MathOverflow)
+}
+
+/**
+ * Computes the amount the recipient receives from claiming now, after fees and withholding.
+ *
+ * The claim fee of the schedule is routed to the treasury and the withholding to the tax
+ * account, see `claim_split`, the rest reaches the recipient in full: mints with a Token-2022
+ * transfer fee are rejected at init, so no transfer fee is ever withheld from a claim.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - The claimable amount minus the claim fee and the withholding.
+ */
+pub fn net_claimable_amount(state: &VestingState, now: i64) -> VestingResult<u64> {
+    let (_, _, recipient_amount) = claim_split(state, claimable_amount(state, now))?;
+    Ok(recipient_amount)
+}
+
+/**
+ * Splits a claim between the treasury, the tax account and the recipient.
+ *
+ * The claim fee is taken first, `claim_fee_bps` of the claimed amount. Tax is withheld from what
+ * the recipient is owed after the fee, `withhold_bps` of the remainder, so the protocol fee is
+ * never taxed as the recipient's income. The recipient receives the rest.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `amount`: The claimed amount.
+ *
+ * Returns:
+ * - The claim fee, the withheld tax and the recipient's amount, summing to `amount`.
+ */
+pub fn claim_split(state: &VestingState, amount: u64) -> VestingResult<(u64, u64, u64)> {
+    let fee = claim_fee(amount, state.claim_fee_bps)?;
+    let withheld = claim_fee(amount - fee, state.withhold_bps)?;
+    Ok((fee, withheld, amount - fee - withheld))
+}
+
+/**
+ * Computes the penalty of claiming the rest of a schedule early, see `claim_early`.
+ *
+ * The penalty only applies to the tokens that have not vested yet, tokens that have already
+ * vested are released in full.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - `early_claim_penalty_bps` of the unvested amount.
+ */
+pub fn early_claim_penalty(state: &VestingState, now: i64) -> VestingResult<u64> {
+    let settled = vested_amount(state, now)
+        .max(state.claimed_amount)
+        .min(state.amount);
+    claim_fee(state.amount - settled, state.early_claim_penalty_bps)
+}
+
+/**
+ * Computes the time until more tokens vest, for UIs showing the next unlock.
+ *
+ * Linear and drip schedules stream continuously, so this returns 0 while streaming, or the
+ * time until `vesting_start` before the schedule has started. Stepped schedules return the
+ * time until the next milestone unlocks, and curve schedules on a flat segment the time until
+ * the curve rises again.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - The seconds until the next vest, or `None` when fully vested.
+ */
+pub fn seconds_until_next_vest(state: &VestingState, now: i64) -> Option<i64> {
+    if vested_amount(state, now) >= state.amount {
+        return None;
+    }
+    // Compare on the vesting clock, a pause before the start delays it by the paused time
+    let vesting_now = vesting_clock(state, now);
+    if vesting_now < state.vesting_start {
+        return Some(state.vesting_start.saturating_sub(vesting_now));
+    }
+    if state.step_count > 0 {
+        let next_milestone = passed_milestones(state, vesting_now);
+        return Some(milestone_unlock_at(state, next_milestone) - vesting_now);
+    }
+    if state.period_seconds > 0 {
+        let elapsed = vesting_now - state.vesting_start;
+        return Some(state.period_seconds - elapsed % state.period_seconds);
+    }
+    if state.curve_point_count > 0 {
+        let rising_from = curve_points(state)
+            .windows(2)
+            .find(|segment| segment[1].0 > vesting_now && segment[1].1 > segment[0].1)
+            .map(|segment| segment[0].0)?;
+        return Some(rising_from.saturating_sub(vesting_now).max(0));
+    }
+    Some(0)
+}
+
+/**
+ * Estimates how many claim transactions it takes to receive the rest of a schedule.
+ *
+ * Claims are not capped per transaction nor rate limited, a single claim at or after the end of
+ * the schedule transfers everything left, so this is 1 for any schedule with tokens left to
+ * claim.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ *
+ * Returns:
+ * - The number of claims needed, or 0 once the schedule is fully claimed.
+ */
+pub fn estimate_claim_count(state: &VestingState) -> u64 {
+    if state.claimed_amount >= state.amount {
+        return 0;
+    }
+    1
+}
+
+/**
+ * Computes the outcome of a claim at the given timestamp, without performing it.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `now`: The current Unix timestamp.
+ *
+ * Returns:
+ * - The amount to transfer, the claimed total afterwards and whether the schedule is then
+ *   fully claimed, or `VestingError::MathOverflow` if the claimed total would overflow.
+ */
+pub fn compute_claim_outcome(state: &VestingState, now: i64) -> VestingResult<ClaimOutcome> {
+    let transferred = claimable_amount(state, now);
+    let total_claimed = state
+        .claimed_amount
+        .checked_add(transferred)
+        .ok_or(VestingError::MathOverflow)?;
+    Ok(ClaimOutcome {
+        transferred,
+        total_claimed,
+        is_final: total_claimed == state.amount,
+    })
+}
+
+/**
+ * Computes the outcome of claiming a single milestone at the given timestamp, without
+ * performing it.
+ *
+ * Parameters:
+ * - `state`: The vesting state, which must be a stepped schedule.
+ * - `now`: The current Unix timestamp.
+ * - `index`: The milestone index.
+ *
+ * Returns:
+ * - The claim outcome of the milestone, `VestingError::MilestoneNotReached` if it has not
+ *   unlocked yet or `VestingError::MilestoneAlreadyClaimed` if it was claimed before.
+ */
+pub fn compute_milestone_claim_outcome(
+    state: &VestingState,
+    now: i64,
+    index: u8,
+) -> Result<ClaimOutcome, ProgramError> {
+    if index >= state.step_count {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if index >= passed_milestones(state, vesting_clock(state, now)) {
+        return Err(VestingError::MilestoneNotReached.into());
+    }
+    if is_milestone_claimed(state, index) {
+        return Err(VestingError::MilestoneAlreadyClaimed.into());
+    }
+
+    let transferred = milestone_amount(state, index);
+    let total_claimed = state
+        .claimed_amount
+        .checked_add(transferred)
+        .ok_or(VestingError::MathOverflow)?;
+    Ok(ClaimOutcome {
+        transferred,
+        total_claimed,
+        is_final: total_claimed == state.amount,
+    })
+}
+
+/**
+ * Initializes a vesting schedule.
+ *
+ * This function transfers the specified amount of tokens from the funder's account
+ * to a vault account and records the vesting details in the vesting state account.
+ * The vesting state includes the recipient, funder, amount, vesting start and end times.
+ * The vesting start time defaults to the current timestamp and may be scheduled in the future,
+ * at which point the TGE portion becomes claimable. With a nonzero `rate_per_second` the
+ * schedule instead drips at a fixed rate with no fixed end until the vault is drained.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account to be initialized.
+ * 1. `[writable]` The vault account to hold the vested tokens.
+ * 2. `[signer]` The funder's account, from which tokens will be transferred, or the SPL token
+ *   multisig owning the funder's token account if `multisig_signer_count` is set.
+ * 3. `[]` The recipient's account, which will receive the tokens after vesting.
+ * 4. `[]` The SPL token program account.
+ * 5. `[]` The Rent sysvar.
+ * 6. `[]` The Clock sysvar.
+ * 7. `[]` The token mint, which is recorded along with its decimals for claims.
+ * 8. `[]` The program config PDA, see `find_config_address`.
+ * 9. `[writable]` The funder quota PDA of the funder, see `find_funder_quota_address`, only if
+ *   the program config sets `max_schedules_per_funder`. The following indices shift by one.
+ * 9. `[writable]` The funder's token account, owned by the multisig at index 2, from which tokens
+ *   will be transferred, only if the funder is an SPL token multisig. The following indices
+ *   shift by one.
+ * 9..M. `[signer]` The signers of the funder, one per `multisig_signer_count`, if the funder is
+ *   an SPL token multisig, at least its threshold of them, see `check_multisig_signers`.
+ * M..N. Pairs of co-funder accounts, one pair per entry of `co_funder_amounts`:
+ *   - `[writable]` The co-funder's token account, from which its share will be transferred.
+ *   - `[signer]` The owner of the co-funder's token account.
+ *
+ * Co-funders contribute their share of `amount` in the same transaction, e.g. co-investors
+ * funding a schedule together, and the funder at index 2 funds the rest. Only the funder at
+ * index 2 is recorded and authorizes later funder operations.
+ *
+ * Schedules are created inactive, nothing can be claimed until the funder calls `activate`,
+ * e.g. after legal signoff. The vesting clock runs from init regardless.
+ *
+ * The vault must be owned by the vault authority PDA of the vesting state account, see
+ * `find_vault_authority`, and be its associated token account if the program config sets
+ * `require_ata_vault`, see `vault_address`. The claim fee and treasury are snapshotted from the
+ * program config, the SPL token program must be one of its allowed token programs and the schedule
+ * must last at least its `min_duration` and at most its `max_duration`. With a
+ * `max_schedules_per_funder` cap the funder can have at most that many open schedules, counted in
+ * its funder quota until `close_schedule` or `merge_vesting` closes them. Once the program config
+ * is `deprecated` init fails with `VestingError::Deprecated`.
+ *
+ * Parameters:
+ * - `params.amount`: The amount of tokens to be vested, or with `amount_in_bps` the share of the
+ *   funder's token balance, in basis points, to be vested.
+ * - `params.vesting_start`: The Unix timestamp when the vesting period starts, not in the past,
+ *   or 0 to start now.
+ * - `params.vesting_end`: The Unix timestamp when the vesting period ends, or with
+ *   `end_is_duration` the vesting period in seconds from the start, ignored for drip schedules.
+ * - `params.tge_bps`: The share of `amount`, in basis points, unlocked at init (TGE).
+ * - `params.recovery_authority`: The authority allowed to redirect the schedule if the
+ *   recipient's key is compromised, or the default pubkey to opt out.
+ * - `params.rate_per_second`: The drip rate in tokens per second, or 0 for a linear schedule.
+ * - `params.step_count`: The number of milestones of a stepped schedule, at most
+ *   `MAX_MILESTONES`, or 0 for a continuous schedule.
+ * - `params.claim_hook`: The program notified after every claim, or the default pubkey to opt
+ *   out.
+ * - `params.unit_size`: The granularity, in base units, of intermediate claims, or 0 to stream
+ *   every base unit. Not supported for stepped or drip schedules.
+ * - `params.amount_in_bps`: Whether `amount` is given in basis points of the funder's current
+ *   token balance. The resulting absolute amount is recorded and locked like any other.
+ * - `params.dual_control`: Whether claims require the signature of the funder on top of the
+ *   receiver's, for high-value grants.
+ * - `params.end_is_duration`: Whether `vesting_end` is given as a positive duration in seconds,
+ *   the recorded end is then computed from the current timestamp.
+ * - `params.irrevocable`: Whether the schedule can never be revoked or recovered, giving the
+ *   recipient on-chain assurance of its terms.
+ * - `params.nft`: Whether the schedule vests a single NFT, a mint with 0 decimals and a supply
+ *   of 1. The NFT is released all at once at `vesting_end`, as a stepped schedule with a single
+ *   milestone, so `amount` must be 1 and TGE, drip, steps and unit size are not supported.
+ * - `params.claim_expiry`: The Unix timestamp after which claims fail and the funder may reclaim
+ *   the unclaimed tokens with `reclaim_expired`, at or after `vesting_end`, or 0 for no expiry.
+ * - `params.period_seconds`: The unlock period in seconds of a periodic schedule, e.g. a day, or
+ *   0 for a continuous schedule. The recorded end is computed from the start as the time of the
+ *   last unlock, so `vesting_end` is ignored, and TGE, drip, steps and unit size
+ *   are not supported.
+ * - `params.per_period`: The amount unlocked at every period of a periodic schedule, at most the
+ *   absolute amount, or 0 for a continuous schedule.
+ * - `params.kyc_required`: Whether claims require a current KYC attestation of the recipient by
+ *   the KYC verifier of the program config, which is snapshotted into the schedule.
+ * - `params.lock_after_first_claim`: Whether the recipient can no longer be redirected by a
+ *   recovery once anything has been claimed.
+ * - `params.launch`: The launch PDA the schedule starts vesting from, see `init_launch`, or the
+ *   default pubkey to start at `vesting_start`. The schedule is pending until `apply_launch`
+ *   sets its start to the launch timestamp, so `vesting_start` must be 0, the end must be given
+ *   as a duration (or follow from the periods) and drip and claim expiry are not supported.
+ * - `params.auto_claim`: Whether anyone may push vested tokens to the receiver's associated
+ *   token account with `crank_claim`, without the receiver's signature. Not supported with dual
+ *   control, a crank cannot sign for the funder.
+ * - `params.withhold_bps`: The share, in basis points, of every claim withheld for tax after the
+ *   claim fee, e.g. for payroll, or 0 to pay the recipient everything, see `claim_split`.
+ * - `params.tax_account`: The token account of the schedule mint receiving withheld tax, or the
+ *   default pubkey if nothing is withheld.
+ * - `params.allow_early_claim`: Whether the recipient may claim the rest of the schedule before
+ *   it vests with `claim_early`. Not supported with dual control or a claim hook.
+ * - `params.early_claim_penalty_bps`: The share, in basis points, of the unvested tokens forfeited
+ *   to the funder on an early claim, or 0 for no penalty, see `early_claim_penalty`.
+ * - `params.claim_governance`: The governance program whose CPI claims must come from, e.g. for
+ *   DAO grants claimed through proposals, see `check_claim_governance`, or the default pubkey to
+ *   let claims be sent directly. Not supported with auto or early claims.
+ * - `params.curve_points`: The `(timestamp, cumulative_amount)` control points of a curve
+ *   schedule, see `curve_vested_amount`, or empty for any other schedule. Between 2 and
+ *   `MAX_CURVE_POINTS` points with increasing timestamps, the first not in the past, and
+ *   non-decreasing amounts ending at the absolute amount. The start and end are those of the
+ *   first and last point, so `vesting_start` must be 0 and `vesting_end` is ignored, and TGE,
+ *   drip, steps, periods, NFTs and launches are not supported.
+ * - `params.multisig_signer_count`: The number of signers of an SPL token multisig funder, at
+ *   most `spl_token::instruction::MAX_SIGNERS`, or 0 if the funder signs itself.
+ * - `params.co_funder_amounts`: The amounts contributed by each co-funder, summing to at most
+ *   the absolute amount.
+ */
+pub fn init_vesting(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: InitVestingParams,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let recipient_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    // Validate everything up front so a failure leaves the accounts untouched
+    let config = load_config(program_id, config_info)?;
+    if config.deprecated {
+        return Err(VestingError::Deprecated.into());
+    }
+    if !config
+        .allowed_token_programs
+        .iter()
+        .any(|token_program| token_program == token_program_info.key)
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if vesting_state_info.data_len() < VestingState::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if !rent.is_exempt(vesting_state_info.lamports(), vesting_state_info.data_len()) {
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    // A multisig funder cannot sign itself, its signers are validated below
+    if params.multisig_signer_count == 0 && !funder_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Validate if the program has been initialized before
+    if !vesting_state_info
+        .try_borrow_data()?
+        .iter()
+        .all(|&byte| byte == 0)
+    {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Validate the funder stays within its schedule cap
+    let quota_info = if config.max_schedules_per_funder > 0 {
+        let quota_info = next_account_info(account_info_iter)?;
+        let quota = load_funder_quota(program_id, quota_info, funder_info.key)?;
+        if quota.active_schedules >= config.max_schedules_per_funder {
+            return Err(VestingError::FunderQuotaExceeded.into());
+        }
+        Some((quota_info, quota))
+    } else {
+        None
+    };
+
+    // A multisig funder only authorizes the transfer out of a separate token account it owns
+    let funder_token_info = if params.multisig_signer_count > 0 {
+        next_account_info(account_info_iter)?
+    } else {
+        funder_info
+    };
+
+    // Resolve a share of the funder's balance into the absolute amount to lock
+    let amount = if params.amount_in_bps {
+        if params.amount > BPS_DENOMINATOR {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let funder_token =
+            spl_token::state::Account::unpack(&funder_token_info.try_borrow_data()?)?;
+        (funder_token.amount as u128 * params.amount as u128 / BPS_DENOMINATOR as u128) as u64
+    } else {
+        params.amount
+    };
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate the funder's multisig signers precede the co-funders
+    if params.multisig_signer_count as usize > spl_token::instruction::MAX_SIGNERS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let remaining_infos = account_info_iter.as_slice();
+    if remaining_infos.len() < params.multisig_signer_count as usize {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let (multisig_signer_infos, co_funder_infos) =
+        remaining_infos.split_at(params.multisig_signer_count as usize);
+    if params.multisig_signer_count > 0 {
+        check_multisig_signers(funder_info, token_program_info, multisig_signer_infos)?;
+    }
+
+    // Validate the co-funders' shares leave the rest to the funder
+    if co_funder_infos.len() != params.co_funder_amounts.len() * 2 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let mut co_funded = 0u64;
+    for (&co_funder_amount, co_funder_pair) in params
+        .co_funder_amounts
+        .iter()
+        .zip(co_funder_infos.chunks_exact(2))
+    {
+        if co_funder_amount == 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !co_funder_pair[1].is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        co_funded = co_funded
+            .checked_add(co_funder_amount)
+            .ok_or(VestingError::MathOverflow)?;
+    }
+    let funder_amount = amount
+        .checked_sub(co_funded)
+        .ok_or(ProgramError::InvalidArgument)?;
+
+    // Validate the TGE share does not exceed 100%
+    if params.tge_bps as u64 > BPS_DENOMINATOR {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Drip schedules have no fixed end and no TGE portion
+    let is_drip = params.rate_per_second > 0;
+    if is_drip && params.tge_bps > 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate stepped schedules fit the milestone bitmap and have a fixed end
+    if params.step_count > MAX_MILESTONES || (is_drip && params.step_count > 0) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Milestones release fixed tranches, they cannot also be rounded to whole units. Drip
+    // schedules restart from every claim, so rounding would drop the remainder on each claim
+    if params.unit_size > 0 && (params.step_count > 0 || is_drip) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate curve schedules rise through their control points to the full amount
+    let is_curve = !params.curve_points.is_empty();
+    if is_curve
+        && (params.curve_points.len() < 2
+            || params.curve_points.len() > MAX_CURVE_POINTS
+            || params.curve_points[0].0 < clock.unix_timestamp
+            || params
+                .curve_points
+                .windows(2)
+                .any(|segment| segment[1].0 <= segment[0].0 || segment[1].1 < segment[0].1)
+            || params.curve_points.last().map(|point| point.1) != Some(amount)
+            || params.vesting_start != 0
+            || params.tge_bps > 0
+            || is_drip
+            || params.step_count > 0
+            || params.period_seconds > 0)
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Resolve the start, a schedule can start later but not in the past
+    let vesting_start = if is_curve {
+        params.curve_points[0].0
+    } else if params.vesting_start == 0 {
+        clock.unix_timestamp
+    } else if params.vesting_start < clock.unix_timestamp {
+        return Err(ProgramError::InvalidArgument);
+    } else {
+        params.vesting_start
+    };
+
+    // Validate periodic schedules unlock a fixed amount on a plain period grid
+    let is_periodic = params.period_seconds > 0;
+    if params.period_seconds < 0
+        || is_periodic != (params.per_period > 0)
+        || (is_periodic
+            && (params.per_period > amount
+                || params.tge_bps > 0
+                || is_drip
+                || params.step_count > 0
+                || params.unit_size > 0))
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let vesting_end = if is_drip {
+        i64::MAX
+    } else if is_curve {
+        params.curve_points[params.curve_points.len() - 1].0
+    } else if is_periodic {
+        let periods = i64::try_from(amount.div_ceil(params.per_period))
+            .map_err(|_| VestingError::MathOverflow)?;
+        periods
+            .checked_mul(params.period_seconds)
+            .and_then(|duration| vesting_start.checked_add(duration))
+            .ok_or(VestingError::MathOverflow)?
+    } else if params.end_is_duration {
+        if params.vesting_end <= 0 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        vesting_start
+            .checked_add(params.vesting_end)
+            .ok_or(VestingError::MathOverflow)?
+    } else {
+        params.vesting_end
+    };
+
+    // Validate the schedule ends after it starts
+    if vesting_end <= vesting_start {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate withheld tax does not exceed 100% and has somewhere to go
+    if params.withhold_bps as u64 > BPS_DENOMINATOR
+        || (params.withhold_bps > 0 && params.tax_account == Pubkey::default())
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate early claims are opted into and can be signed by the receiver alone
+    if params.early_claim_penalty_bps as u64 > BPS_DENOMINATOR
+        || (params.early_claim_penalty_bps > 0 && !params.allow_early_claim)
+        || (params.allow_early_claim
+            && (params.dual_control || params.claim_hook != Pubkey::default()))
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate governed claims cannot be made around the governance program
+    if params.claim_governance != Pubkey::default()
+        && (params.auto_claim || params.allow_early_claim)
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate cranked claims need no signature of the funder
+    if params.auto_claim && params.dual_control {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate launch schedules have a timeline relative to the launch
+    let has_launch = params.launch != Pubkey::default();
+    if has_launch
+        && (params.vesting_start != 0
+            || is_drip
+            || is_curve
+            || params.claim_expiry != 0
+            || !(params.end_is_duration || is_periodic))
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate KYC can only be required with a verifier to attest it
+    let kyc_verifier = if params.kyc_required {
+        if config.kyc_verifier == Pubkey::default() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        config.kyc_verifier
+    } else {
+        Pubkey::default()
+    };
+
+    // Validate the claim window stays open at least until the schedule is fully vested
+    if params.claim_expiry != 0
+        && (params.claim_expiry <= clock.unix_timestamp
+            || (!is_drip && params.claim_expiry < vesting_end))
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate the schedule lasts within the configured bounds, drip schedules until drained
+    let duration = if is_drip {
+        (amount / params.rate_per_second) as i64
+    } else {
+        vesting_end - vesting_start
+    };
+    if duration < config.min_duration {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.max_duration > 0 && duration > config.max_duration {
+        return Err(VestingError::DurationTooLong.into());
+    }
+
+    // Snapshot the mint decimals so claims can validate the mint they are passed
+    if mint_info.owner != token_program_info.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mint = spl_token::state::Mint::unpack(&mint_info.try_borrow_data()?)?;
+
+    // Validate NFTs are vested whole, they cannot stream
+    if params.nft
+        && (mint.decimals != 0
+            || mint.supply != 1
+            || amount != 1
+            || params.tge_bps > 0
+            || is_drip
+            || params.step_count > 0
+            || params.unit_size > 0
+            || is_periodic
+            || is_curve)
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let step_count = if params.nft { 1 } else { params.step_count };
+    let mut curve_points = [(0i64, 0u64); MAX_CURVE_POINTS];
+    curve_points[..params.curve_points.len()].copy_from_slice(&params.curve_points);
+
+    // Keep launch schedules pending until the launch is applied, see `is_launch_pending`
+    let (vesting_start, vesting_end, launch_duration) = if has_launch {
+        (i64::MAX, i64::MAX, vesting_end - vesting_start)
+    } else {
+        (vesting_start, vesting_end, 0)
+    };
+
+    // Validate the vault is held by the dedicated vault authority PDA
+    check_distinct_accounts(&[vesting_state_info, vault_info, funder_token_info])?;
+    for co_funder_pair in co_funder_infos.chunks_exact(2) {
+        check_distinct_accounts(&[vesting_state_info, vault_info, &co_funder_pair[0]])?;
+    }
+    let (vault_authority, vault_authority_bump) =
+        find_vault_authority(vesting_state_info.key, program_id);
+    if vault_info.owner != token_program_info.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if config.require_ata_vault {
+        let (ata_vault, _) = vault_address(
+            vesting_state_info.key,
+            mint_info.key,
+            token_program_info.key,
+            program_id,
+        );
+        if *vault_info.key != ata_vault {
+            return Err(ProgramError::InvalidSeeds);
+        }
+    }
+    let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+    if vault.owner != vault_authority {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if !rent.is_exempt(vault_info.lamports(), spl_token::state::Account::LEN) {
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    if vault.mint != *mint_info.key {
+        return Err(VestingError::MintMismatch.into());
+    }
+    if vault.delegate.is_some() {
+        return Err(VestingError::UnexpectedDelegate.into());
+    }
+
+    let mut vesting_state = VestingState {
+        is_initialized: true,
+        receiver: *recipient_info.key,
+        funder: *funder_info.key,
+        amount,
+        vesting_start,
+        vesting_end,
+        claimed_amount: 0,
+        tge_bps: params.tge_bps,
+        recovery_authority: params.recovery_authority,
+        recovery_receiver: Pubkey::default(),
+        recovery_requested_at: 0,
+        rate_per_second: params.rate_per_second,
+        last_claim_at: vesting_start,
+        decimals: mint.decimals,
+        vault_authority_bump,
+        claim_fee_bps: config.claim_fee_bps,
+        treasury: config.treasury,
+        mint: *mint_info.key,
+        claim_hook: params.claim_hook,
+        checkpoint_at: vesting_start,
+        checkpoint_vested: 0,
+        step_count,
+        claimed_milestones: 0,
+        paused_at: 0,
+        paused_duration: 0,
+        is_frozen: false,
+        schedule_hash: Hash::default(),
+        unit_size: params.unit_size,
+        dual_control: params.dual_control,
+        irrevocable: params.irrevocable,
+        pending_funder: Pubkey::default(),
+        refund_owner: Pubkey::default(),
+        active: false,
+        claim_expiry: params.claim_expiry,
+        quota_owner: Pubkey::default(),
+        period_seconds: params.period_seconds,
+        per_period: params.per_period,
+        kyc_verifier,
+        lock_after_first_claim: params.lock_after_first_claim,
+        launch: params.launch,
+        launch_duration,
+        auto_claim: params.auto_claim,
+        curve_point_count: params.curve_points.len() as u8,
+        curve_points,
+        withhold_bps: params.withhold_bps,
+        tax_account: params.tax_account,
+        allow_early_claim: params.allow_early_claim,
+        early_claim_penalty_bps: params.early_claim_penalty_bps,
+        claim_governance: params.claim_governance,
+        vault: *vault_info.key,
+    };
+    vesting_state.checkpoint_vested = tge_portion(&vesting_state);
+    vesting_state.schedule_hash = schedule_hash(&vesting_state);
+
+    // Count the schedule against the funder's cap until it is closed
+    if let Some((quota_info, mut quota)) = quota_info {
+        quota.active_schedules = quota
+            .active_schedules
+            .checked_add(1)
+            .ok_or(VestingError::MathOverflow)?;
+        quota.pack_into_slice(&mut quota_info.try_borrow_mut_data()?);
+        vesting_state.quota_owner = *funder_info.key;
+    }
+
+    // Write vesting state to account
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    // Transfer vested tokens to the vault account, one transfer per funding source. Every
+    // transfer out of the funders' accounts happens here, after all validation has passed, so a
+    // rejected init never moves tokens
+    if funder_amount > 0 {
+        let multisig_signers: Vec<&Pubkey> = multisig_signer_infos
+            .iter()
+            .map(|signer_info| signer_info.key)
+            .collect();
+        let transfer_ix = spl_token::instruction::transfer(
+            token_program_info.key,
+            funder_token_info.key,
+            vault_info.key,
+            funder_info.key,
+            &multisig_signers,
+            funder_amount,
+        )?;
+        let mut transfer_infos = vec![
+            funder_token_info.clone(),
+            vault_info.clone(),
+            funder_info.clone(),
+            token_program_info.clone(),
+        ];
+        transfer_infos.extend(multisig_signer_infos.iter().cloned());
+        invoke(&transfer_ix, &transfer_infos)?;
+    }
+    for (&co_funder_amount, co_funder_pair) in params
+        .co_funder_amounts
+        .iter()
+        .zip(co_funder_infos.chunks_exact(2))
+    {
+        let (co_funder_token_info, co_funder_info) = (&co_funder_pair[0], &co_funder_pair[1]);
+        let transfer_ix = spl_token::instruction::transfer(
+            token_program_info.key,
+            co_funder_token_info.key,
+            vault_info.key,
+            co_funder_info.key,
+            &[],
+            co_funder_amount,
+        )?;
+        invoke(
+            &transfer_ix,
+            &[
+                co_funder_token_info.clone(),
+                vault_info.clone(),
+                co_funder_info.clone(),
+                token_program_info.clone(),
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Initializes a vesting schedule unless the same schedule has already been initialized.
+ *
+ * This function makes init safe to retry: if the vesting state account already holds a schedule
+ * with the requested terms, e.g. because an earlier attempt landed but its confirmation was
+ * lost, it succeeds without doing anything. A vesting state account holding different terms
+ * still fails with `AccountAlreadyInitialized`, see `matches_init_params`. Otherwise it behaves
+ * exactly like `init_vesting`.
+ *
+ * Accounts expected by this instruction are the same as for `init_vesting`.
+ *
+ * Parameters:
+ * - `params`: The init parameters, see `init_vesting`.
+ */
+pub fn init_idempotent(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: InitVestingParams,
+) -> ProgramResult {
+    let [vesting_state_info, _, funder_info, recipient_info, _, _, _, mint_info, ..] = accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if vesting_state_info.owner == program_id
+        && !vesting_state_info
+            .try_borrow_data()?
+            .iter()
+            .all(|&byte| byte == 0)
+    {
+        let vesting_state =
+            VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+        if vesting_state.receiver == *recipient_info.key
+            && vesting_state.funder == *funder_info.key
+            && vesting_state.mint == *mint_info.key
+            && matches_init_params(&vesting_state, &params)
+        {
+            return Ok(());
+        }
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    init_vesting(program_id, accounts, params)
+}
+
+/**
+ * Tests whether a schedule was created with the given init parameters.
+ *
+ * Parameters given relative to the time of init cannot be recomputed later, so they only match
+ * what they resolve to independently of it: a `vesting_start` of 0 matches any start, and with
+ * `end_is_duration` the end has to lie `vesting_end` seconds after the start, or be recorded as
+ * the `launch_duration` of a launch schedule. An amount given in
+ * basis points of the funder's balance never matches, the balance has changed since. Co-funder
+ * and multisig parameters only affect funding and are not compared.
+ *
+ * Parameters:
+ * - `state`: The vesting state.
+ * - `params`: The init parameters.
+ */
+pub fn matches_init_params(state: &VestingState, params: &InitVestingParams) -> bool {
+    let is_drip = params.rate_per_second > 0;
+    let is_periodic = params.period_seconds > 0;
+    let is_curve = !params.curve_points.is_empty();
+    let end_matches = if is_drip || is_periodic || is_curve {
+        true
+    } else if params.launch != Pubkey::default() {
+        state.launch_duration == params.vesting_end
+    } else if params.end_is_duration {
+        state.vesting_end.checked_sub(state.vesting_start) == Some(params.vesting_end)
+    } else {
+        state.vesting_end == params.vesting_end
+    };
+    let step_count = if params.nft { 1 } else { params.step_count };
+
+    !params.amount_in_bps
+        && state.amount == params.amount
+        && (params.vesting_start == 0 || state.vesting_start == params.vesting_start)
+        && end_matches
+        && state.tge_bps == params.tge_bps
+        && state.recovery_authority == params.recovery_authority
+        && state.rate_per_second == params.rate_per_second
+        && state.claim_hook == params.claim_hook
+        && state.step_count == step_count
+        && state.unit_size == params.unit_size
+        && state.dual_control == params.dual_control
+        && state.irrevocable == params.irrevocable
+        && state.claim_expiry == params.claim_expiry
+        && state.period_seconds == params.period_seconds
+        && state.per_period == params.per_period
+        && (state.kyc_verifier != Pubkey::default()) == params.kyc_required
+        && state.lock_after_first_claim == params.lock_after_first_claim
+        && state.launch == params.launch
+        && state.auto_claim == params.auto_claim
+        && curve_points(state) == params.curve_points.as_slice()
+        && state.withhold_bps == params.withhold_bps
+        && state.tax_account == params.tax_account
+        && state.allow_early_claim == params.allow_early_claim
+        && state.early_claim_penalty_bps == params.early_claim_penalty_bps
+        && state.claim_governance == params.claim_governance
+}
+
+/**
+ * Claims the vested tokens.
+ *
+ * This function allows the recipient to claim whatever has vested so far: the TGE portion
+ * once vesting starts, then the linear stream of the remainder until the vesting period ends.
+ * Claims before a future `vesting_start` fail with `NothingVestedYet`, see `starts_at`.
+ * It transfers the claimable tokens from the vault account to the recipient's account and
+ * records them in `claimed_amount`. If the schedule has a claim fee, `claim_fee_bps` of the
+ * claimed tokens go to the treasury instead, and if it withholds tax, `withhold_bps` of the rest
+ * go to the tax account, see `claim_split`. Once everything is claimed the vesting state is marked
+ * as uninitialized to prevent further claims, see `is_closed_out`, and any residual vault dust
+ * (up to `vault_dust_threshold`) is returned to the funder with the vault closed. Larger residuals,
+ * e.g. from over-funding, keep the vault open until the funder sweeps them with `close_vault`.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[writable]` The vault account holding the vested tokens.
+ * 2. `[writable]` The recipient's token account, owned by the receiver, which will receive the
+ *   tokens.
+ * 3. `[]` The SPL token program account.
+ * 4. `[]` The Clock sysvar.
+ * 5. `[writable]` The funder's token account, which receives residual vault tokens.
+ * 6. `[writable]` The funder's account, which receives the vault rent when it is closed.
+ * 7. `[]` The token mint recorded at init.
+ * 8. `[]` The vault authority PDA, which signs the transfer out of the vault.
+ * 9. `[signer]` The receiver, except for claims of `crank_claim`.
+ * 10. `[]` The KYC attestation of the receiver, only if the schedule requires KYC, see
+ *   `check_kyc_attestation`.
+ * 11. `[]` The Instructions sysvar, only if the schedule has a claim governance program, see
+ *   `check_claim_governance`.
+ * 12. `[]` The SPL Memo program, only if a memo is given.
+ * 13. `[writable]` The treasury token account, only if the schedule has a claim fee.
+ * 14. `[writable]` The tax account, only if the schedule withholds tax.
+ * 15. `[]` The claim hook program, only if the schedule has a claim hook.
+ * 16. `[writable]` The claim ledger PDA of the schedule, see `init_claim_ledger`, optional.
+ *
+ * Optional accounts that do not apply are left out, the following ones move up. Claims must be
+ * signed by the receiver, `crank_claim` is the only way to claim without its signature. Schedules
+ * with dual control can only be claimed with the signatures of both the receiver and the funder,
+ * the funder signing as account 6.
+ *
+ * If the schedule registered a claim hook, the hook program is invoked after the transfer with
+ * the vesting state account and the encoded `ClaimOutcome` as instruction data.
+ *
+ * If a claim ledger is passed, the claim is appended to it as `(timestamp, transferred)`, keeping
+ * the last `CLAIM_LEDGER_CAPACITY` claims for audits. The ledger must be the claim ledger PDA of
+ * the schedule, see `find_claim_ledger_address`, created beforehand with `init_claim_ledger`.
+ *
+ * If a memo is given, it is logged by the SPL Memo program right before the transfer to the
+ * recipient, e.g. for exchange deposit addresses that require a memo.
+ *
+ * For self-vesting schedules the recipient's and the funder's token accounts may be the same.
+ * The receiver does not have to be a wallet, a token account owned by a PDA or multisig recorded
+ * as the receiver is accepted as well.
+ *
+ * Parameters:
+ * - `memo`: The UTF-8 memo of at most `MAX_CLAIM_MEMO_LEN` bytes, or `None` for no memo.
+ */
+pub fn claim_vesting(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    memo: Option<&str>,
+) -> ProgramResult {
+    claim(program_id, accounts, None, memo, false)
+}
+
+/**
+ * Claims a single milestone of a stepped schedule.
+ *
+ * This function lets the recipient claim exactly the tokens of one reached milestone, so
+ * tranches can be claimed individually and in any order. The claimed milestone is recorded in
+ * the `claimed_milestones` bitmap, a regular `claim_vesting` claims all reached milestones at
+ * once. Claim fees, the claim hook and the final close apply as for `claim_vesting`.
+ *
+ * Accounts expected by this instruction are the same as for `claim_vesting`.
+ *
+ * Parameters:
+ * - `index`: The 0-based index of the milestone to claim.
+ * - `memo`: The memo of the transfer, see `claim_vesting`.
+ */
+pub fn claim_milestone(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    index: u8,
+    memo: Option<&str>,
+) -> ProgramResult {
+    claim(program_id, accounts, Some(index), memo, false)
+}
+
+/**
+ * Claims the vested tokens on behalf of the receiver.
+ *
+ * This function lets anyone, e.g. a payroll crank, push whatever has vested so far to the
+ * receiver of a schedule created with `auto_claim`, so the receiver does not have to claim
+ * itself. The tokens can only go to the receiver's associated token account for the schedule
+ * mint, see `find_associated_token_address`, a crank cannot redirect them. This is the only way to
+ * claim without the receiver's signature.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[signer]` The crank triggering the claim.
+ * 1..N. The accounts of `claim_vesting`, without a memo and without the receiver, with the
+ *   receiver's associated token account as the recipient's token account.
+ */
+pub fn crank_claim(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let (crank_info, claim_infos) = accounts
+        .split_first()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let [vesting_state_info, _, recipient_info, token_program_info, ..] = claim_infos else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !crank_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.auto_claim {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (recipient_ata, _) = find_associated_token_address(
+        &vesting_state.receiver,
+        &vesting_state.mint,
+        token_program_info.key,
+    );
+    if *recipient_info.key != recipient_ata {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    claim(program_id, claim_infos, None, None, true)
+}
+
+/**
+ * Claims vested tokens, either everything claimable or a single milestone.
+ *
+ * Parameters:
+ * - `milestone`: The milestone to claim, or `None` to claim everything claimable.
+ * - `memo`: The memo of the transfer, or `None` for no memo.
+ * - `cranked`: Whether the claim comes from `crank_claim`, which needs no receiver signature.
+ */
+fn claim(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    milestone: Option<u8>,
+    memo: Option<&str>,
+    cranked: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let recipient_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let funder_token_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+
+    // The vesting state is program data, passing it as the vault or recipient would only fail
+    // once it is unpacked as a token account
+    check_distinct_accounts(&[vesting_state_info, vault_info, recipient_info])?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        // A closed-out schedule simply has nothing left to claim
+        if is_closed_out(&vesting_state) {
+            return Err(VestingError::NothingToClaim.into());
+        }
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if !vesting_state.active {
+        return Err(VestingError::NotActive.into());
+    }
+    if is_claim_expired(&vesting_state, clock.unix_timestamp) {
+        return Err(VestingError::ClaimExpired.into());
+    }
+    check_vault_authority(
+        program_id,
+        vault_authority_info,
+        vesting_state_info,
+        &vesting_state,
+    )?;
+
+    // Flag a vesting state left below the rent-exempt minimum by a rent change, see `top_up_rent`
+    if !Rent::get()?.is_exempt(vesting_state_info.lamports(), vesting_state_info.data_len()) {
+        msg!("Vesting state is not rent-exempt, top it up with top_up_rent");
+    }
+
+    // Validate the mint against the decimals snapshotted at init
+    if *mint_info.key != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let mint = spl_token::state::Mint::unpack(&mint_info.try_borrow_data()?)?;
+    if mint.decimals != vesting_state.decimals {
+        return Err(VestingError::DecimalsMismatch.into());
+    }
+
+    // Validate the tokens are sent to a token account of the recorded receiver, whatever kind of
+    // address it is
+    check_distinct_accounts(&[vault_info, funder_token_info])?;
+    let recipient_token = spl_token::state::Account::unpack(&recipient_info.try_borrow_data()?)?;
+    if recipient_token.owner != vesting_state.receiver {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if recipient_token.is_frozen() {
+        return Err(VestingError::RecipientFrozen.into());
+    }
+
+    // Validate both sides of the transfer hold the schedule mint
+    if vault_info.owner != token_program_info.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+    if vault.mint != vesting_state.mint || recipient_token.mint != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    // A delegate could move vault tokens behind the schedule's back
+    if vault.delegate.is_some() {
+        return Err(VestingError::UnexpectedDelegate.into());
+    }
+
+    // Validate the claim is signed by the receiver unless cranked, init rules out cranking
+    // dual-control schedules
+    if !cranked {
+        let receiver_info = next_account_info(account_info_iter)?;
+        if !receiver_info.is_signer || *receiver_info.key != vesting_state.receiver {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+    }
+
+    // Validate dual-control claims are also signed by the funder
+    if vesting_state.dual_control
+        && (!funder_info.is_signer || *funder_info.key != vesting_state.funder)
+    {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Validate the receiver holds a current KYC attestation of the schedule's verifier
+    if vesting_state.kyc_verifier != Pubkey::default() {
+        let attestation_info = next_account_info(account_info_iter)?;
+        check_kyc_attestation(attestation_info, &vesting_state, clock.unix_timestamp)?;
+    }
+
+    // Validate claims of a governed schedule are invoked by its governance program
+    if vesting_state.claim_governance != Pubkey::default() {
+        let instructions_info = next_account_info(account_info_iter)?;
+        check_claim_governance(instructions_info, &vesting_state)?;
+    }
+
+    // Validate the memo program precedes the treasury when a memo is given
+    let memo = match memo {
+        Some(memo) => {
+            let memo_program_info = next_account_info(account_info_iter)?;
+            if *memo_program_info.key != SPL_MEMO_PROGRAM_ID {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            Some((memo, memo_program_info))
+        }
+        None => None,
+    };
+
+    // Verify there is anything vested that has not been claimed yet
+    let outcome = match milestone {
+        Some(index) => {
+            compute_milestone_claim_outcome(&vesting_state, clock.unix_timestamp, index)?
+        }
+        None => compute_claim_outcome(&vesting_state, clock.unix_timestamp)?,
+    };
+    if outcome.transferred == 0 {
+        if starts_at(&vesting_state, clock.unix_timestamp).is_some() {
+            return Err(VestingError::NothingVestedYet.into());
+        }
+        return Err(VestingError::NothingToClaim.into());
+    }
+
+    let bump = [vesting_state.vault_authority_bump];
+    let vault_signer_seeds = vault_authority_seeds(vesting_state_info.key, &bump);
+
+    // Route the claim fee to the treasury
+    let (fee, withheld, recipient_amount) = claim_split(&vesting_state, outcome.transferred)?;
+    if vesting_state.claim_fee_bps > 0 {
+        let treasury_info = next_account_info(account_info_iter)?;
+        if *treasury_info.key != vesting_state.treasury {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_distinct_accounts(&[vault_info, treasury_info])?;
+        if fee > 0 {
+            let fee_ix = spl_token::instruction::transfer_checked(
+                token_program_info.key,
+                vault_info.key,
+                mint_info.key,
+                treasury_info.key,
+                vault_authority_info.key,
+                &[],
+                fee,
+                vesting_state.decimals,
+            )?;
+            invoke_signed(
+                &fee_ix,
+                &[
+                    vault_info.clone(),
+                    mint_info.clone(),
+                    treasury_info.clone(),
+                    vault_authority_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[&vault_signer_seeds],
+            )?;
+        }
+    }
+
+    // Withhold tax from the recipient's share
+    if vesting_state.withhold_bps > 0 {
+        let tax_info = next_account_info(account_info_iter)?;
+        if *tax_info.key != vesting_state.tax_account {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_distinct_accounts(&[vault_info, tax_info])?;
+        if withheld > 0 {
+            let tax_ix = spl_token::instruction::transfer_checked(
+                token_program_info.key,
+                vault_info.key,
+                mint_info.key,
+                tax_info.key,
+                vault_authority_info.key,
+                &[],
+                withheld,
+                vesting_state.decimals,
+            )?;
+            invoke_signed(
+                &tax_ix,
+                &[
+                    vault_info.clone(),
+                    mint_info.clone(),
+                    tax_info.clone(),
+                    vault_authority_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[&vault_signer_seeds],
+            )?;
+        }
+    }
+
+    // Attach the memo to the transfer to the recipient
+    if let Some((memo, memo_program_info)) = memo {
+        let memo_ix = Instruction {
+            program_id: SPL_MEMO_PROGRAM_ID,
+            accounts: vec![],
+            data: memo.as_bytes().to_vec(),
+        };
+        invoke(&memo_ix, &[memo_program_info.clone()])?;
+    }
+
+    // Transfer vested tokens to the recipient, signed by the vault authority
+    let transfer_ix = spl_token::instruction::transfer_checked(
+        token_program_info.key,
+        vault_info.key,
+        mint_info.key,
+        recipient_info.key,
+        vault_authority_info.key,
+        &[],
+        recipient_amount,
+        vesting_state.decimals,
+    )?;
+    invoke_signed(
+        &transfer_ix,
+        &[
+            vault_info.clone(),
+            mint_info.clone(),
+            recipient_info.clone(),
+            vault_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&vault_signer_seeds],
+    )?;
+
+    // Record the claim, marking the vesting state as not initialized once fully claimed
+    let mut new_vesting_state = vesting_state;
+    new_vesting_state.claimed_amount = outcome.total_claimed;
+    new_vesting_state.last_claim_at = clock.unix_timestamp;
+    let claimed_milestones = match milestone {
+        Some(index) => milestone_bit(index),
+        None => milestone_mask(passed_milestones(
+            &new_vesting_state,
+            vesting_clock(&new_vesting_state, clock.unix_timestamp),
+        )),
+    };
+    set_milestones_claimed(&mut new_vesting_state, claimed_milestones);
+    if outcome.is_final {
+        new_vesting_state.is_initialized = false;
+    }
+    new_vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    msg!(
+        "Claimed {} tokens, {} of {} claimed in total",
+        outcome.transferred,
+        outcome.total_claimed,
+        new_vesting_state.amount
+    );
+
+    // Return any dust left in the vault to the funder once the recipient is fully paid, larger
+    // residuals are left for the funder to sweep with `close_vault`
+    let residual = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?.amount;
+    if outcome.is_final && residual <= vault_dust_threshold(&new_vesting_state) {
+        sweep_vault(
+            &new_vesting_state,
+            vault_authority_info,
+            vault_info,
+            funder_token_info,
+            funder_info,
+            token_program_info,
+            &vault_signer_seeds,
+        )?;
+    }
+
+    // Notify the claim hook of the outcome
+    if new_vesting_state.claim_hook != Pubkey::default() {
+        let hook_program_info = next_account_info(account_info_iter)?;
+        if *hook_program_info.key != new_vesting_state.claim_hook {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let hook_ix = Instruction {
+            program_id: new_vesting_state.claim_hook,
+            accounts: vec![AccountMeta::new_readonly(*vesting_state_info.key, false)],
+            data: outcome.to_bytes().to_vec(),
+        };
+        invoke(
+            &hook_ix,
+            &[vesting_state_info.clone(), hook_program_info.clone()],
+        )?;
+    }
+
+    // Append the claim to the claim ledger
+    if let Some(ledger_info) = account_info_iter.next() {
+        let mut ledger = load_claim_ledger(program_id, ledger_info, vesting_state_info.key)?;
+        ledger.record(clock.unix_timestamp, outcome.transferred);
+        ledger.pack_into_slice(&mut ledger_info.try_borrow_mut_data()?);
+    }
+
+    // Set return data last, CPIs reset it
+    set_return_data(&outcome.to_bytes());
+
+    Ok(())
+}
+
+/**
+ * Sweeps the residual vault balance back to the funder.
+ *
+ * This helper transfers whatever is left in the vault to the funder's token account and,
+ * if the vault is owned by the vault authority PDA, closes it and returns its rent to
+ * the funder. The vault balance is re-read after the transfer so the vault is never closed
+ * while it still holds tokens.
+ *
+ * Parameters:
+ * - `vesting_state`: The vesting state the vault belongs to.
+ * - `vault_signer_seeds`: The seeds used to sign for the vault authority PDA.
+ */
+fn sweep_vault<'a>(
+    vesting_state: &VestingState,
+    vault_authority_info: &AccountInfo<'a>,
+    vault_info: &AccountInfo<'a>,
+    funder_token_info: &AccountInfo<'a>,
+    funder_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+    vault_signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    // Validate the dust goes back to the funder of this schedule
+    let funder_token = spl_token::state::Account::unpack(&funder_token_info.try_borrow_data()?)?;
+    if funder_token.owner != vesting_state.funder || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+    if vault.amount > 0 {
+        let transfer_ix = spl_token::instruction::transfer(
+            token_program_info.key,
+            vault_info.key,
+            funder_token_info.key,
+            vault_authority_info.key,
+            &[],
+            vault.amount,
+        )?;
+        invoke_signed(
+            &transfer_ix,
+            &[
+                vault_info.clone(),
+                funder_token_info.clone(),
+                vault_authority_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[vault_signer_seeds],
+        )?;
+    }
+
+    // Close the vault if it is owned by the program's vault authority and fully swept
+    if vault.owner == *vault_authority_info.key {
+        let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+        if vault.amount > 0 {
+            return Err(VestingError::VaultNotEmpty.into());
+        }
+        let close_ix = spl_token::instruction::close_account(
+            token_program_info.key,
+            vault_info.key,
+            funder_info.key,
+            vault_authority_info.key,
+            &[],
+        )?;
+        invoke_signed(
+            &close_ix,
+            &[
+                vault_info.clone(),
+                funder_info.clone(),
+                vault_authority_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[vault_signer_seeds],
+        )?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Splits a vesting schedule into two.
+ *
+ * This function carves `split_amount` out of the remaining unvested tokens of a schedule into
+ * a new vesting state account for a new recipient, preserving the same end. Both schedules are
+ * checkpointed at the current timestamp, so the new position streams exactly `split_amount`
+ * from now to the end and the source schedule keeps its currently vested tokens. The carved
+ * tokens are moved from the source vault to the new vault. The new schedule's `schedule_hash`
+ * covers its own terms, the source schedule keeps the hash it was created with.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The source vesting state account.
+ * 1. `[writable]` The new vesting state account to be initialized.
+ * 2. `[writable]` The source vault account.
+ * 3. `[writable]` The new vault account, owned by the new vault authority PDA.
+ * 4. `[signer]` The recipient of the source schedule.
+ * 5. `[]` The new recipient's account.
+ * 6. `[]` The SPL token program account.
+ * 7. `[]` The Rent sysvar.
+ * 8. `[]` The Clock sysvar.
+ * 9. `[]` The source vault authority PDA.
+ *
+ * Parameters:
+ * - `split_amount`: The amount of unvested tokens to move into the new schedule.
+ */
+pub fn split_vesting(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    split_amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let new_vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let new_vault_info = next_account_info(account_info_iter)?;
+    let recipient_info = next_account_info(account_info_iter)?;
+    let new_recipient_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    check_vault_authority(
+        program_id,
+        vault_authority_info,
+        vesting_state_info,
+        &vesting_state,
+    )?;
+
+    // Validate the split is authorized by the current recipient
+    if !recipient_info.is_signer || *recipient_info.key != vesting_state.receiver {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if new_vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if new_vesting_state_info.data_len() < VestingState::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if !rent.is_exempt(
+        new_vesting_state_info.lamports(),
+        new_vesting_state_info.data_len(),
+    ) {
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    if !new_vesting_state_info
+        .try_borrow_data()?
+        .iter()
+        .all(|&byte| byte == 0)
+    {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Validate the new vault is held by the new schedule's vault authority PDA
+    check_distinct_accounts(&[
+        vesting_state_info,
+        new_vesting_state_info,
+        vault_info,
+        new_vault_info,
+    ])?;
+    let (new_vault_authority, new_vault_authority_bump) =
+        find_vault_authority(new_vesting_state_info.key, program_id);
+    let new_vault = spl_token::state::Account::unpack(&new_vault_info.try_borrow_data()?)?;
+    if new_vault.owner != new_vault_authority {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if new_vault.mint != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    // Drip, stepped, periodic and curve schedules have no shared linear stream to carve a
+    // position out of
+    if vesting_state.rate_per_second > 0
+        || vesting_state.step_count > 0
+        || vesting_state.period_seconds > 0
+        || vesting_state.curve_point_count > 0
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Vested tokens have to be claimed first, so only unvested tokens get carved out
+    let now = clock.unix_timestamp;
+    if claimable_amount(&vesting_state, now) > 0 {
+        return Err(VestingError::UnclaimedVestedTokens.into());
+    }
+
+    // Validate the split amount does not exceed the remaining unvested tokens
+    checkpoint(&mut vesting_state, now);
+    let unvested = vesting_state.amount - vesting_state.checkpoint_vested;
+    if split_amount == 0 || split_amount > unvested {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The new position streams `split_amount` from the current checkpoint to the shared end
+    let mut new_vesting_state = VestingState {
+        is_initialized: true,
+        receiver: *new_recipient_info.key,
+        funder: vesting_state.funder,
+        amount: split_amount,
+        vesting_start: vesting_state.vesting_start,
+        vesting_end: vesting_state.vesting_end,
+        claimed_amount: 0,
+        tge_bps: vesting_state.tge_bps,
+        recovery_authority: vesting_state.recovery_authority,
+        recovery_receiver: Pubkey::default(),
+        recovery_requested_at: 0,
+        rate_per_second: 0,
+        last_claim_at: vesting_state.last_claim_at,
+        decimals: vesting_state.decimals,
+        vault_authority_bump: new_vault_authority_bump,
+        claim_fee_bps: vesting_state.claim_fee_bps,
+        treasury: vesting_state.treasury,
+        mint: vesting_state.mint,
+        claim_hook: vesting_state.claim_hook,
+        checkpoint_at: vesting_state.checkpoint_at,
+        checkpoint_vested: 0,
+        step_count: 0,
+        claimed_milestones: 0,
+        paused_at: vesting_state.paused_at,
+        paused_duration: vesting_state.paused_duration,
+        is_frozen: false,
+        schedule_hash: Hash::default(),
+        unit_size: vesting_state.unit_size,
+        dual_control: vesting_state.dual_control,
+        irrevocable: vesting_state.irrevocable,
+        pending_funder: Pubkey::default(),
+        refund_owner: vesting_state.refund_owner,
+        active: vesting_state.active,
+        claim_expiry: vesting_state.claim_expiry,
+        quota_owner: Pubkey::default(),
+        period_seconds: 0,
+        per_period: 0,
+        kyc_verifier: vesting_state.kyc_verifier,
+        lock_after_first_claim: vesting_state.lock_after_first_claim,
+        launch: vesting_state.launch,
+        launch_duration: vesting_state.launch_duration,
+        auto_claim: vesting_state.auto_claim,
+        curve_point_count: 0,
+        curve_points: [(0, 0); MAX_CURVE_POINTS],
+        withhold_bps: vesting_state.withhold_bps,
+        tax_account: vesting_state.tax_account,
+        allow_early_claim: vesting_state.allow_early_claim,
+        early_claim_penalty_bps: vesting_state.early_claim_penalty_bps,
+        claim_governance: vesting_state.claim_governance,
+        vault: *new_vault_info.key,
+    };
+    new_vesting_state.schedule_hash = schedule_hash(&new_vesting_state);
+
+    let mut updated_vesting_state = vesting_state;
+    updated_vesting_state.amount -= split_amount;
+
+    updated_vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+    new_vesting_state.pack_into_slice(&mut new_vesting_state_info.try_borrow_mut_data()?);
+
+    // Move the carved tokens to the new vault
+    let bump = [updated_vesting_state.vault_authority_bump];
+    let vault_signer_seeds = vault_authority_seeds(vesting_state_info.key, &bump);
+    let transfer_ix = spl_token::instruction::transfer(
+        token_program_info.key,
+        vault_info.key,
+        new_vault_info.key,
+        vault_authority_info.key,
+        &[],
+        split_amount,
+    )?;
+    invoke_signed(
+        &transfer_ix,
+        &[
+            vault_info.clone(),
+            new_vault_info.clone(),
+            vault_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&vault_signer_seeds],
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Merges two vesting schedules with identical parameters.
+ *
+ * This function combines the source schedule into the target schedule, summing their amounts
+ * and claimed amounts, and moves the source vault balance into the target vault. Both
+ * schedules must share the same mint, recipient, funder, start, end, TGE share, unit size and
+ * checkpoint, so schedules that were split or partially revoked can only be merged with their
+ * own kind. The source vesting state account and vault are closed with their rent returned to
+ * the funder.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The target vesting state account.
+ * 1. `[writable]` The source vesting state account, closed by this instruction.
+ * 2. `[writable]` The target vault account.
+ * 3. `[writable]` The source vault account, closed by this instruction.
+ * 4. `[signer]` The recipient of both schedules.
+ * 5. `[writable]` The funder's account, which receives the closed accounts' rent.
+ * 6. `[]` The SPL token program account.
+ * 7. `[]` The source vault authority PDA.
+ * 8. `[writable]` The funder quota PDA of the source schedule's `quota_owner`, only if the source
+ *   schedule counts against a funder's schedule cap.
+ */
+pub fn merge_vesting(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let source_vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let source_vault_info = next_account_info(account_info_iter)?;
+    let recipient_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let source_vault_authority_info = next_account_info(account_info_iter)?;
+    let quota_info = account_info_iter.next();
+
+    check_distinct_accounts(&[
+        vesting_state_info,
+        source_vesting_state_info,
+        vault_info,
+        source_vault_info,
+    ])?;
+
+    if vesting_state_info.owner != program_id || source_vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    let source_vesting_state =
+        VestingState::unpack_from_slice(&source_vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized || !source_vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen || source_vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    check_vault_authority(
+        program_id,
+        source_vault_authority_info,
+        source_vesting_state_info,
+        &source_vesting_state,
+    )?;
+
+    // Validate the merge is authorized by the recipient of both schedules
+    if !recipient_info.is_signer || *recipient_info.key != vesting_state.receiver {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Validate the source tokens go into the target schedule's vault
+    let (vault_authority, _) = find_vault_authority(vesting_state_info.key, program_id);
+    let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+    if vault.owner != vault_authority {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Validate both schedules vest the same mint on the same terms
+    let source_vault = spl_token::state::Account::unpack(&source_vault_info.try_borrow_data()?)?;
+    if vault.mint != source_vault.mint
+        || vesting_state.mint != source_vesting_state.mint
+        || vesting_state.receiver != source_vesting_state.receiver
+        || vesting_state.funder != source_vesting_state.funder
+        || vesting_state.vesting_start != source_vesting_state.vesting_start
+        || vesting_state.vesting_end != source_vesting_state.vesting_end
+        || vesting_state.tge_bps != source_vesting_state.tge_bps
+        || vesting_state.recovery_authority != source_vesting_state.recovery_authority
+        || vesting_state.claim_fee_bps != source_vesting_state.claim_fee_bps
+        || vesting_state.treasury != source_vesting_state.treasury
+        || vesting_state.claim_hook != source_vesting_state.claim_hook
+        || vesting_state.checkpoint_at != source_vesting_state.checkpoint_at
+        || vesting_state.paused_at != source_vesting_state.paused_at
+        || vesting_state.paused_duration != source_vesting_state.paused_duration
+        || vesting_state.unit_size != source_vesting_state.unit_size
+        || vesting_state.dual_control != source_vesting_state.dual_control
+        || vesting_state.irrevocable != source_vesting_state.irrevocable
+        || vesting_state.refund_owner != source_vesting_state.refund_owner
+        || vesting_state.active != source_vesting_state.active
+        || vesting_state.claim_expiry != source_vesting_state.claim_expiry
+        || vesting_state.kyc_verifier != source_vesting_state.kyc_verifier
+        || vesting_state.lock_after_first_claim != source_vesting_state.lock_after_first_claim
+        || vesting_state.launch != source_vesting_state.launch
+        || vesting_state.launch_duration != source_vesting_state.launch_duration
+        || vesting_state.auto_claim != source_vesting_state.auto_claim
+        || vesting_state.withhold_bps != source_vesting_state.withhold_bps
+        || vesting_state.tax_account != source_vesting_state.tax_account
+        || vesting_state.allow_early_claim != source_vesting_state.allow_early_claim
+        || vesting_state.early_claim_penalty_bps != source_vesting_state.early_claim_penalty_bps
+        || vesting_state.claim_governance != source_vesting_state.claim_governance
+    {
+        return Err(VestingError::ScheduleMismatch.into());
+    }
+
+    // Merging drip schedules would slow down the combined release, stepped schedules would mix
+    // up their claimed milestones, periodic schedules their per-period amounts and curve
+    // schedules their control points
+    if vesting_state.rate_per_second > 0
+        || source_vesting_state.rate_per_second > 0
+        || vesting_state.step_count > 0
+        || source_vesting_state.step_count > 0
+        || vesting_state.period_seconds > 0
+        || source_vesting_state.period_seconds > 0
+        || vesting_state.curve_point_count > 0
+        || source_vesting_state.curve_point_count > 0
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut merged_vesting_state = vesting_state;
+    merged_vesting_state.amount = merged_vesting_state
+        .amount
+        .checked_add(source_vesting_state.amount)
+        .ok_or(VestingError::MathOverflow)?;
+    merged_vesting_state.claimed_amount = merged_vesting_state
+        .claimed_amount
+        .checked_add(source_vesting_state.claimed_amount)
+        .ok_or(VestingError::MathOverflow)?;
+    merged_vesting_state.checkpoint_vested = merged_vesting_state
+        .checkpoint_vested
+        .checked_add(source_vesting_state.checkpoint_vested)
+        .ok_or(VestingError::MathOverflow)?;
+    merged_vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    // Move the source vault balance into the target vault and close the source vault
+    let bump = [source_vesting_state.vault_authority_bump];
+    let source_signer_seeds = vault_authority_seeds(source_vesting_state_info.key, &bump);
+    if source_vault.amount > 0 {
+        let transfer_ix = spl_token::instruction::transfer(
+            token_program_info.key,
+            source_vault_info.key,
+            vault_info.key,
+            source_vault_authority_info.key,
+            &[],
+            source_vault.amount,
+        )?;
+        invoke_signed(
+            &transfer_ix,
+            &[
+                source_vault_info.clone(),
+                vault_info.clone(),
+                source_vault_authority_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&source_signer_seeds],
+        )?;
+    }
+    let close_ix = spl_token::instruction::close_account(
+        token_program_info.key,
+        source_vault_info.key,
+        funder_info.key,
+        source_vault_authority_info.key,
+        &[],
+    )?;
+    invoke_signed(
+        &close_ix,
+        &[
+            source_vault_info.clone(),
+            funder_info.clone(),
+            source_vault_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&source_signer_seeds],
+    )?;
+
+    release_funder_quota(program_id, quota_info, &source_vesting_state)?;
+    close_vesting_state(source_vesting_state_info, funder_info)
+}
+
+/**
+ * Closes a vesting state account.
+ *
+ * This helper zeroes the account data and moves all of its lamports to the destination
+ * account, so the runtime garbage-collects it at the end of the transaction.
+ *
+ * Parameters:
+ * - `vesting_state_info`: The vesting state account to close.
+ * - `destination_info`: The account receiving the rent.
+ */
+fn close_vesting_state(
+    vesting_state_info: &AccountInfo,
+    destination_info: &AccountInfo,
+) -> ProgramResult {
+    let lamports = vesting_state_info.lamports();
+    **vesting_state_info.try_borrow_mut_lamports()? = 0;
+    **destination_info.try_borrow_mut_lamports()? = destination_info
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidArgument)?;
+    vesting_state_info.try_borrow_mut_data()?.fill(0);
+
+    Ok(())
+}
+
+/**
+ * Requests recovery of a vesting schedule.
+ *
+ * This function lets the recovery authority, if one was set at init, schedule redirecting the
+ * remaining tokens to a new recipient in case the current recipient's key is compromised. The
+ * redirect can only be executed with `recover` once `RECOVERY_DELAY` has elapsed. Schedules
+ * whose recipient is locked cannot be recovered, see `is_recipient_locked`.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[signer]` The recovery authority.
+ * 2. `[]` The new recipient's account.
+ * 3. `[]` The Clock sysvar.
+ */
+pub fn request_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let recovery_authority_info = next_account_info(account_info_iter)?;
+    let new_recipient_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if vesting_state.irrevocable {
+        return Err(VestingError::Irrevocable.into());
+    }
+    if is_recipient_locked(&vesting_state) {
+        return Err(VestingError::RecipientLocked.into());
+    }
+
+    // Validate recovery is enabled and requested by the recovery authority
+    if vesting_state.recovery_authority == Pubkey::default()
+        || !recovery_authority_info.is_signer
+        || *recovery_authority_info.key != vesting_state.recovery_authority
+    {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    vesting_state.recovery_receiver = *new_recipient_info.key;
+    vesting_state.recovery_requested_at = clock.unix_timestamp;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Executes a pending recovery of a vesting schedule.
+ *
+ * This function redirects the remaining vested and unvested tokens to the recipient named in the
+ * pending recovery request, once `RECOVERY_DELAY` has elapsed since it was requested. A request
+ * made before the recipient got locked by their first claim can no longer be executed.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[signer]` The recovery authority.
+ * 2. `[]` The Clock sysvar.
+ */
+pub fn recover(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let recovery_authority_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if vesting_state.irrevocable {
+        return Err(VestingError::Irrevocable.into());
+    }
+    if is_recipient_locked(&vesting_state) {
+        return Err(VestingError::RecipientLocked.into());
+    }
+
+    if vesting_state.recovery_authority == Pubkey::default()
+        || !recovery_authority_info.is_signer
+        || *recovery_authority_info.key != vesting_state.recovery_authority
+    {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if vesting_state.recovery_requested_at == 0 {
+        return Err(VestingError::NoRecoveryRequested.into());
+    }
+
+    // Enforce the delay so the recipient can notice a pending recovery
+    let recoverable_at = vesting_state
+        .recovery_requested_at
+        .checked_add(RECOVERY_DELAY)
+        .ok_or(VestingError::MathOverflow)?;
+    if clock.unix_timestamp < recoverable_at {
+        return Err(VestingError::RecoveryDelayNotElapsed.into());
+    }
+
+    vesting_state.receiver = vesting_state.recovery_receiver;
+    vesting_state.recovery_receiver = Pubkey::default();
+    vesting_state.recovery_requested_at = 0;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Reads the claimable amounts of many schedules at once.
+ *
+ * This read-only function writes the claimable amount of every vesting state account passed
+ * after the Clock sysvar as an array of little-endian `u64` values to the return data, in
+ * account order. Fully claimed schedules report 0. At most `MAX_BULK_CLAIMABLE` (128) schedules
+ * fit in the 1024 bytes of return data.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[]` The Clock sysvar.
+ * 1..N. `[]` The vesting state accounts to read.
+ */
+pub fn read_claimable_amounts(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let vesting_state_infos = account_info_iter.as_slice();
+
+    if vesting_state_infos.len() > MAX_BULK_CLAIMABLE {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut return_data = Vec::with_capacity(vesting_state_infos.len() * 8);
+    for vesting_state_info in vesting_state_infos {
+        if vesting_state_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let vesting_state =
+            VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+        let claimable = if vesting_state.is_initialized {
+            claimable_amount(&vesting_state, clock.unix_timestamp)
+        } else {
+            0
+        };
+        return_data.extend_from_slice(&claimable.to_le_bytes());
+    }
+    set_return_data(&return_data);
+
+    Ok(())
+}
+
+/**
+ * Reads what a claim would pay out now, before and after fees.
+ *
+ * This read-only function writes the gross claimable amount followed by the net amount the
+ * recipient receives, see `net_claimable_amount`, to the return data, both as little-endian
+ * `u64` values, so UIs can show the figure that actually lands in the recipient's account.
+ * Fully claimed schedules report 0 for both.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[]` The vesting state account to read.
+ * 1. `[]` The Clock sysvar.
+ */
+pub fn read_claim_preview(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    let (gross, net) = if vesting_state.is_initialized {
+        (
+            claimable_amount(&vesting_state, clock.unix_timestamp),
+            net_claimable_amount(&vesting_state, clock.unix_timestamp)?,
+        )
+    } else {
+        (0, 0)
+    };
+    let mut return_data = [0u8; 16];
+    return_data[0..8].copy_from_slice(&gross.to_le_bytes());
+    return_data[8..16].copy_from_slice(&net.to_le_bytes());
+    set_return_data(&return_data);
+
+    Ok(())
+}
+
+/**
+ * Reads whether a schedule currently has anything to claim.
+ *
+ * This read-only function writes a single byte to the return data, `1` if the recipient can
+ * claim any tokens now and `0` otherwise, so wallets can cheaply decide whether to offer a
+ * claim. Fully claimed, frozen, inactive and expired schedules report `0`.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[]` The vesting state account to read.
+ * 1. `[]` The Clock sysvar.
+ */
+pub fn read_is_claimable(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    let is_claimable = vesting_state.is_initialized
+        && !vesting_state.is_frozen
+        && vesting_state.active
+        && !is_claim_expired(&vesting_state, clock.unix_timestamp)
+        && claimable_amount(&vesting_state, clock.unix_timestamp) > 0;
+    set_return_data(&[is_claimable as u8]);
+
+    Ok(())
+}
+
+/**
+ * Reads when a schedule is fully vested, accounting for the time it has been paused.
+ *
+ * This read-only function writes the effective end of the schedule, see
+ * `effective_vesting_end`, followed by the seconds left until then (0 once reached) to the
+ * return data, both as little-endian `i64` values. While the schedule is paused the effective
+ * end moves out with every second, UIs showing "fully vests in X" should re-read it.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[]` The vesting state account to read.
+ * 1. `[]` The Clock sysvar.
+ */
+pub fn read_effective_end(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let now = clock.unix_timestamp;
+    let effective_end = effective_vesting_end(&vesting_state, now);
+    let remaining = effective_end.saturating_sub(now).max(0);
+    let mut return_data = [0u8; 16];
+    return_data[0..8].copy_from_slice(&effective_end.to_le_bytes());
+    return_data[8..16].copy_from_slice(&remaining.to_le_bytes());
+    set_return_data(&return_data);
+
+    Ok(())
+}
+
+/**
+ * Reads the vesting curve of a schedule as sampled points, for charting.
+ *
+ * This read-only function writes `sample_count` samples of the schedule's cumulative vested
+ * amount, evenly spaced from `start` to `end`, to the return data, see `sample_vesting_curve`.
+ * Each sample is a little-endian `i64` timestamp followed by a little-endian `u64` vested
+ * amount. At most `MAX_CURVE_SAMPLES` (64) samples fit in the 1024 bytes of return data.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[]` The vesting state account to read.
+ *
+ * Parameters:
+ * - `params.start`: The Unix timestamp of the first sample.
+ * - `params.end`: The Unix timestamp of the last sample, at or after `start`.
+ * - `params.sample_count`: The number of samples, between 1 and `MAX_CURVE_SAMPLES`.
+ */
+pub fn read_vesting_curve(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: CurveParams,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+
+    if params.sample_count == 0
+        || params.sample_count as usize > MAX_CURVE_SAMPLES
+        || params.end < params.start
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let samples = sample_vesting_curve(
+        &vesting_state,
+        params.start,
+        params.end,
+        params.sample_count,
+    );
+    let mut return_data = Vec::with_capacity(samples.len() * 16);
+    for (timestamp, vested) in samples {
+        return_data.extend_from_slice(&timestamp.to_le_bytes());
+        return_data.extend_from_slice(&vested.to_le_bytes());
+    }
+    set_return_data(&return_data);
+
+    Ok(())
+}
+
+/**
+ * Reads the milestones of a stepped schedule, for rendering its unlock calendar.
+ *
+ * This read-only function writes up to `MAX_MILESTONE_ENTRIES` (56) milestones starting at
+ * `first` to the return data, see `list_milestones`. Each entry is the `u8` milestone index, the
+ * little-endian `i64` unlock timestamp, the little-endian `u64` amount and a `u8` that is `1` if
+ * the milestone was claimed. Schedules with more milestones are read in pages by passing the
+ * index after the last one returned as `first`.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[]` The vesting state account to read.
+ * 1. `[]` The Clock sysvar.
+ *
+ * Parameters:
+ * - `first`: The index of the first milestone to read.
+ */
+pub fn read_milestones(program_id: &Pubkey, accounts: &[AccountInfo], first: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.step_count == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let milestones = list_milestones(
+        &vesting_state,
+        clock.unix_timestamp,
+        first,
+        MAX_MILESTONE_ENTRIES,
+    );
+    let mut return_data = Vec::with_capacity(milestones.len() * 18);
+    for (index, unlock_at, amount, claimed) in milestones {
+        return_data.push(index);
+        return_data.extend_from_slice(&unlock_at.to_le_bytes());
+        return_data.extend_from_slice(&amount.to_le_bytes());
+        return_data.push(claimed as u8);
+    }
+    set_return_data(&return_data);
+
+    Ok(())
+}
+
+/**
+ * Revokes part of the unvested tokens of a vesting schedule.
+ *
+ * This function lets the funder take back `revoke_amount` of the tokens that have not vested yet,
+ * reducing `amount` while the rest keeps vesting. Linear schedules are checkpointed first, so
+ * what has vested so far is unaffected and the reduced remainder streams from now until
+ * `vesting_end`. Drip schedules keep their rate and simply drain sooner. If nothing is left
+ * for the recipient to claim afterwards, the schedule is closed out like a final claim.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[writable]` The vault account holding the vested tokens.
+ * 2. `[writable, signer]` The funder of the schedule.
+ * 3. `[writable]` The token account receiving the revoked tokens, owned by the funder or, if the
+ *    schedule records one, the refund owner, see `revoke_refund_owner`.
+ * 4. `[]` The vault authority PDA, which signs the transfer out of the vault.
+ * 5. `[]` The token mint recorded at init.
+ * 6. `[]` The SPL token program account.
+ * 7. `[]` The Clock sysvar.
+ *
+ * Parameters:
+ * - `revoke_amount`: The amount of unvested tokens to return to the funder.
+ */
+pub fn revoke_partial(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    revoke_amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let funder_token_info = next_account_info(account_info_iter)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if vesting_state.irrevocable {
+        return Err(VestingError::Irrevocable.into());
+    }
+    check_vault_authority(
+        program_id,
+        vault_authority_info,
+        vesting_state_info,
+        &vesting_state,
+    )?;
+
+    // Validate the revoke is authorized by the funder
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Validate the revoked tokens go back to the funder in the schedule mint
+    check_distinct_accounts(&[vesting_state_info, vault_info, funder_token_info])?;
+    if *mint_info.key != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let funder_token = spl_token::state::Account::unpack(&funder_token_info.try_borrow_data()?)?;
+    if funder_token.owner != revoke_refund_owner(&vesting_state) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+    if vault.mint != vesting_state.mint || funder_token.mint != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    // Shrinking a stepped schedule would resize milestones that were already claimed, a
+    // periodic schedule would keep its end past the last unlock and a curve schedule would no
+    // longer end at its amount
+    if vesting_state.step_count > 0
+        || vesting_state.period_seconds > 0
+        || vesting_state.curve_point_count > 0
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate the revoke amount does not exceed the remaining unvested tokens
+    let now = clock.unix_timestamp;
+    checkpoint(&mut vesting_state, now);
+    let unvested = vesting_state.amount - vested_amount(&vesting_state, now);
+    if revoke_amount == 0 || revoke_amount > unvested {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    vesting_state.amount -= revoke_amount;
+    let is_final = vesting_state.claimed_amount == vesting_state.amount;
+    if is_final {
+        vesting_state.is_initialized = false;
+    }
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    // Return the revoked tokens to the funder, signed by the vault authority
+    let bump = [vesting_state.vault_authority_bump];
+    let vault_signer_seeds = vault_authority_seeds(vesting_state_info.key, &bump);
+    let transfer_ix = spl_token::instruction::transfer_checked(
+        token_program_info.key,
+        vault_info.key,
+        mint_info.key,
+        funder_token_info.key,
+        vault_authority_info.key,
+        &[],
+        revoke_amount,
+        vesting_state.decimals,
+    )?;
+    invoke_signed(
+        &transfer_ix,
+        &[
+            vault_info.clone(),
+            mint_info.clone(),
+            funder_token_info.clone(),
+            vault_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&vault_signer_seeds],
+    )?;
+
+    // Nothing is left for the recipient, return any dust and close the vault
+    let residual = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?.amount;
+    if is_final && residual <= vault_dust_threshold(&vesting_state) {
+        sweep_vault(
+            &vesting_state,
+            vault_authority_info,
+            vault_info,
+            funder_token_info,
+            funder_info,
+            token_program_info,
+            &vault_signer_seeds,
+        )?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Tops up a vesting schedule with more tokens.
+ *
+ * This function lets the funder add `top_up_amount` tokens to a schedule mid-stream. Linear
+ * schedules are checkpointed first, so what has vested so far is unaffected and the new total
+ * streams the remainder from now until `vesting_end`, without a jump in the vested amount that
+ * would let the recipient over-claim. Drip schedules keep their rate and simply drain later.
+ * Stepped schedules and linear schedules past their end cannot be topped up.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[writable]` The vault account holding the vested tokens.
+ * 2. `[signer]` The funder of the schedule.
+ * 3. `[writable]` The funder's token account, from which the tokens are transferred.
+ * 4. `[]` The token mint recorded at init.
+ * 5. `[]` The SPL token program account.
+ * 6. `[]` The Clock sysvar.
+ *
+ * Parameters:
+ * - `top_up_amount`: The amount of tokens to add to the schedule.
+ */
+pub fn top_up_vesting(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    top_up_amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let funder_token_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Validate the tokens go into this schedule's vault in the schedule mint
+    check_distinct_accounts(&[vesting_state_info, vault_info, funder_token_info])?;
+    if *mint_info.key != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let (vault_authority, _) = find_vault_authority(vesting_state_info.key, program_id);
+    let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+    if vault.owner != vault_authority {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if vault.mint != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    // Growing a stepped schedule would resize milestones that were already claimed, a periodic
+    // schedule would unlock the top-up after its end, a curve schedule would never reach it and
+    // a linear schedule past its end has no window left to stream the top-up over
+    let now = clock.unix_timestamp;
+    if top_up_amount == 0
+        || vesting_state.step_count > 0
+        || vesting_state.period_seconds > 0
+        || vesting_state.curve_point_count > 0
+        || vesting_clock(&vesting_state, now) >= vesting_state.vesting_end
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    checkpoint(&mut vesting_state, now);
+    vesting_state.amount = vesting_state
+        .amount
+        .checked_add(top_up_amount)
+        .ok_or(VestingError::MathOverflow)?;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    let transfer_ix = spl_token::instruction::transfer_checked(
+        token_program_info.key,
+        funder_token_info.key,
+        mint_info.key,
+        vault_info.key,
+        funder_info.key,
+        &[],
+        top_up_amount,
+        vesting_state.decimals,
+    )?;
+    invoke(
+        &transfer_ix,
+        &[
+            funder_token_info.clone(),
+            mint_info.clone(),
+            vault_info.clone(),
+            funder_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Initializes the program config.
+ *
+ * This function creates the singleton program config PDA holding the protocol-wide claim fee,
+ * treasury and allowed token programs that `init_vesting` reads. Only the upgrade authority of
+ * the program can initialize it, and pays for its rent.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The program config PDA, see `find_config_address`.
+ * 1. `[writable, signer]` The upgrade authority of the program.
+ * 2. `[]` The program data account of the program.
+ * 3. `[]` The system program.
+ * 4. `[]` The Rent sysvar.
+ *
+ * Parameters:
+ * - `params.claim_fee_bps`: The share of every claim, in basis points, routed to the treasury.
+ * - `params.treasury`: The treasury token account receiving claim fees.
+ * - `params.allowed_token_programs`: The token programs schedules may be created with.
+ * - `params.min_duration`: The minimum schedule duration in seconds, or 0 for no minimum.
+ * - `params.max_schedules_per_funder`: The maximum number of open schedules per funder, or 0 for
+ *   no cap.
+ * - `params.kyc_verifier`: The program owning the KYC attestations of schedules that require
+ *   KYC, or the default pubkey if KYC cannot be required.
+ * - `params.require_ata_vault`: Whether new schedules must use the associated token account of
+ *   their vault authority as the vault, see `vault_address`.
+ * - `params.max_duration`: The maximum schedule duration in seconds, at least `min_duration`, or
+ *   0 for no maximum. Catches schedules whose end was mistyped, e.g. centuries out.
+ * - `params.deprecated`: Whether the program is being sunset. New schedules are rejected with
+ *   `VestingError::Deprecated`, existing ones can still be claimed, revoked and closed so no
+ *   funds get stuck.
+ */
+pub fn init_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: ConfigParams,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let program_data_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+    check_upgrade_authority(program_id, authority_info, program_data_info)?;
+    validate_config_params(&params)?;
+
+    let (config_address, bump) = find_config_address(program_id);
+    if *config_info.key != config_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if config_info.data_len() > 0 {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Create the config account, signed by the config PDA
+    let create_ix = system_instruction::create_account(
+        authority_info.key,
+        config_info.key,
+        rent.minimum_balance(ProgramConfig::LEN),
+        ProgramConfig::LEN as u64,
+        program_id,
+    );
+    invoke_signed(
+        &create_ix,
+        &[
+            authority_info.clone(),
+            config_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[CONFIG_SEED, &[bump]]],
+    )?;
+
+    let config = ProgramConfig {
+        is_initialized: true,
+        bump,
+        claim_fee_bps: params.claim_fee_bps,
+        treasury: params.treasury,
+        allowed_token_programs: params.allowed_token_programs,
+        min_duration: params.min_duration,
+        max_schedules_per_funder: params.max_schedules_per_funder,
+        kyc_verifier: params.kyc_verifier,
+        require_ata_vault: params.require_ata_vault,
+        max_duration: params.max_duration,
+        deprecated: params.deprecated,
+    };
+    config.pack_into_slice(&mut config_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Updates the program config.
+ *
+ * This function replaces the protocol-wide parameters of the program config. Only the upgrade
+ * authority of the program can update it. Existing schedules keep the claim fee and treasury
+ * they were created with. Only schedules created while a schedule cap is set count against it,
+ * and schedules requiring KYC keep the verifier they were created with.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The program config PDA.
+ * 1. `[signer]` The upgrade authority of the program.
+ * 2. `[]` The program data account of the program.
+ *
+ * Parameters:
+ * - `params`: The new config parameters, see `init_config`.
+ */
+pub fn update_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: ConfigParams,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let program_data_info = next_account_info(account_info_iter)?;
+
+    check_upgrade_authority(program_id, authority_info, program_data_info)?;
+    validate_config_params(&params)?;
+
+    let mut config = load_config(program_id, config_info)?;
+    config.claim_fee_bps = params.claim_fee_bps;
+    config.treasury = params.treasury;
+    config.allowed_token_programs = params.allowed_token_programs;
+    config.min_duration = params.min_duration;
+    config.max_schedules_per_funder = params.max_schedules_per_funder;
+    config.kyc_verifier = params.kyc_verifier;
+    config.require_ata_vault = params.require_ata_vault;
+    config.max_duration = params.max_duration;
+    config.deprecated = params.deprecated;
+    config.pack_into_slice(&mut config_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Initializes the funder quota of a funder.
+ *
+ * This function creates the funder quota PDA counting the funder's open schedules, which
+ * `init_vesting` requires while the program config sets a `max_schedules_per_funder` cap. Anyone
+ * can create it on behalf of a funder and pays for its rent.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The funder quota PDA, see `find_funder_quota_address`.
+ * 1. `[writable, signer]` The payer of the rent.
+ * 2. `[]` The funder the quota belongs to.
+ * 3. `[]` The system program.
+ * 4. `[]` The Rent sysvar.
+ */
+pub fn init_funder_quota(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let quota_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (quota_address, bump) = find_funder_quota_address(funder_info.key, program_id);
+    if *quota_info.key != quota_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if quota_info.data_len() > 0 {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Create the funder quota account, signed by the funder quota PDA
+    let create_ix = system_instruction::create_account(
+        payer_info.key,
+        quota_info.key,
+        rent.minimum_balance(FunderQuota::LEN),
+        FunderQuota::LEN as u64,
+        program_id,
+    );
+    invoke_signed(
+        &create_ix,
+        &[
+            payer_info.clone(),
+            quota_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[FUNDER_QUOTA_SEED, funder_info.key.as_ref(), &[bump]]],
+    )?;
+
+    let quota = FunderQuota {
+        is_initialized: true,
+        bump,
+        active_schedules: 0,
+    };
+    quota.pack_into_slice(&mut quota_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Initializes the claim ledger of a schedule.
+ *
+ * This function creates the claim ledger PDA recording the last `CLAIM_LEDGER_CAPACITY` claims
+ * of a schedule, see `claim_vesting`. Anyone can create it on behalf of a schedule and pays for
+ * its rent.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The claim ledger PDA, see `find_claim_ledger_address`.
+ * 1. `[writable, signer]` The payer of the rent.
+ * 2. `[]` The vesting state account the ledger belongs to.
+ * 3. `[]` The system program.
+ * 4. `[]` The Rent sysvar.
+ */
+pub fn init_claim_ledger(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let ledger_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let (ledger_address, bump) = find_claim_ledger_address(vesting_state_info.key, program_id);
+    if *ledger_info.key != ledger_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if ledger_info.data_len() > 0 {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Create the claim ledger account, signed by the claim ledger PDA
+    let create_ix = system_instruction::create_account(
+        payer_info.key,
+        ledger_info.key,
+        rent.minimum_balance(ClaimLedger::LEN),
+        ClaimLedger::LEN as u64,
+        program_id,
+    );
+    invoke_signed(
+        &create_ix,
+        &[
+            payer_info.clone(),
+            ledger_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[CLAIM_LEDGER_SEED, vesting_state_info.key.as_ref(), &[bump]]],
+    )?;
+
+    let ledger = ClaimLedger {
+        account_type: CLAIM_LEDGER_ACCOUNT_TYPE,
+        bump,
+        vesting_state: *vesting_state_info.key,
+        next_index: 0,
+        entry_count: 0,
+        entries: [(0, 0); CLAIM_LEDGER_CAPACITY],
+    };
+    ledger.pack_into_slice(&mut ledger_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Closes the vault of a fully claimed schedule.
+ *
+ * This function lets the funder sweep a residual vault balance that was too large to be swept
+ * as dust on the final claim, returning it to the funder's token account and closing the vault.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[]` The fully claimed vesting state account.
+ * 1. `[writable]` The vault account.
+ * 2. `[writable]` The funder's token account, which receives the residual vault tokens.
+ * 3. `[writable, signer]` The funder of the schedule, which receives the vault rent.
+ * 4. `[]` The vault authority PDA.
+ * 5. `[]` The SPL token program account.
+ */
+pub fn close_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let funder_token_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if vesting_state.is_initialized {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    check_vault_authority(
+        program_id,
+        vault_authority_info,
+        vesting_state_info,
+        &vesting_state,
+    )?;
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_distinct_accounts(&[vault_info, funder_token_info])?;
+
+    let bump = [vesting_state.vault_authority_bump];
+    let vault_signer_seeds = vault_authority_seeds(vesting_state_info.key, &bump);
+    sweep_vault(
+        &vesting_state,
+        vault_authority_info,
+        vault_info,
+        funder_token_info,
+        funder_info,
+        token_program_info,
+        &vault_signer_seeds,
+    )
+}
+
+/**
+ * Rescues tokens mistakenly sent to the vault authority PDA of a schedule.
+ *
+ * This function lets the upgrade authority of the program move the whole balance of a stray
+ * token account owned by the vault authority PDA to a destination of its choice. Only token
+ * accounts of a different mint than the schedule are accepted, since any token account of the
+ * schedule mint owned by the vault authority can serve as its vault. The stray account is left
+ * open, empty.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[]` The vesting state account.
+ * 1. `[writable]` The stray token account, owned by the vault authority PDA.
+ * 2. `[writable]` The token account receiving the rescued tokens.
+ * 3. `[signer]` The upgrade authority of the program.
+ * 4. `[]` The program data account of the program.
+ * 5. `[]` The vault authority PDA, which signs the transfer out of the stray account.
+ * 6. `[]` The SPL token program account.
+ * 7. `[]` The program config PDA.
+ */
+pub fn rescue_tokens(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let stray_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let program_data_info = next_account_info(account_info_iter)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    check_upgrade_authority(program_id, authority_info, program_data_info)?;
+    let config = load_config(program_id, config_info)?;
+    if !config
+        .allowed_token_programs
+        .iter()
+        .any(|token_program| token_program == token_program_info.key)
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    check_vault_authority(
+        program_id,
+        vault_authority_info,
+        vesting_state_info,
+        &vesting_state,
+    )?;
+    check_distinct_accounts(&[stray_info, destination_info])?;
+
+    // Validate the stray account belongs to the vault authority but cannot be the vault
+    if stray_info.owner != token_program_info.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let stray = spl_token::state::Account::unpack(&stray_info.try_borrow_data()?)?;
+    if stray.owner != *vault_authority_info.key || stray.mint == vesting_state.mint {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if stray.amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let destination = spl_token::state::Account::unpack(&destination_info.try_borrow_data()?)?;
+    if destination.mint != stray.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let bump = [vesting_state.vault_authority_bump];
+    let vault_signer_seeds = vault_authority_seeds(vesting_state_info.key, &bump);
+    let transfer_ix = spl_token::instruction::transfer(
+        token_program_info.key,
+        stray_info.key,
+        destination_info.key,
+        vault_authority_info.key,
+        &[],
+        stray.amount,
+    )?;
+    invoke_signed(
+        &transfer_ix,
+        &[
+            stray_info.clone(),
+            destination_info.clone(),
+            vault_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&vault_signer_seeds],
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Closes a vesting schedule that has no pending obligations.
+ *
+ * This function lets the funder close the vesting state account of a schedule once everything
+ * has been claimed, including schedules whose remainder was fully revoked, and its vault holds
+ * no tokens. An empty vault that is still open is closed along with it, the rent of both goes
+ * to the funder. Residual vault tokens have to be swept with `close_vault` first.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account, closed by this instruction.
+ * 1. `[writable]` The vault account recorded at init, either empty or already closed.
+ * 2. `[writable, signer]` The funder of the schedule, which receives the rent.
+ * 3. `[]` The vault authority PDA.
+ * 4. `[]` The SPL token program account.
+ * 5. `[writable]` The funder quota PDA of the schedule's `quota_owner`, only if the schedule
+ *   counts against a funder's schedule cap.
+ */
+pub fn close_schedule(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let quota_info = account_info_iter.next();
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_accounts(&[vesting_state_info, vault_info, funder_info])?;
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    check_vault_authority(
+        program_id,
+        vault_authority_info,
+        vesting_state_info,
+        &vesting_state,
+    )?;
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Validate the recipient has nothing left to claim
+    if vesting_state.claimed_amount != vesting_state.amount {
+        return Err(VestingError::NotFullyClaimed.into());
+    }
+
+    // Validate the vault is the schedule's own, a closed vault has no owner left to check
+    if *vault_info.key != vesting_state.vault {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Close the vault if it is still open, it must not hold any tokens
+    if vault_info.data_len() > 0 {
+        let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+        if vault.owner != *vault_authority_info.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if vault.amount > 0 {
+            return Err(VestingError::VaultNotEmpty.into());
+        }
+        close_empty_vault(
+            vesting_state_info,
+            &vesting_state,
+            vault_info,
+            funder_info,
+            vault_authority_info,
+            token_program_info,
+        )?;
+    }
+
+    release_funder_quota(program_id, quota_info, &vesting_state)?;
+    close_vesting_state(vesting_state_info, funder_info)
+}
+
+/**
+ * Closes many vesting schedules that have no pending obligations.
+ *
+ * This function lets the funder clean up finished schedules in bulk, closing each schedule
+ * that `close_schedule` would close and skipping the others instead of failing, so a single
+ * unfinished schedule does not block the batch. Schedules that are not fully claimed, whose vault
+ * still holds tokens or that count against a funder's schedule cap are skipped, the latter have
+ * to be closed with `close_schedule`. The rent of every closed account goes to the funder.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable, signer]` The funder of the schedules, which receives the rent.
+ * 1. `[]` The SPL token program account.
+ * 2..N. Groups of three accounts, one group per schedule:
+ *   - `[writable]` The vesting state account.
+ *   - `[writable]` The vault account recorded at init, either empty or already closed.
+ *   - `[]` The vault authority PDA.
+ */
+pub fn close_many(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let funder_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if !funder_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let schedule_infos = account_info_iter.as_slice();
+    if schedule_infos.is_empty() || schedule_infos.len() % 3 != 0 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let mut closed = 0;
+    for schedule_group in schedule_infos.chunks_exact(3) {
+        let (vesting_state_info, vault_info, vault_authority_info) =
+            (&schedule_group[0], &schedule_group[1], &schedule_group[2]);
+        if vesting_state_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        check_distinct_accounts(&[vesting_state_info, vault_info, funder_info])?;
+        let vesting_state =
+            VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+        check_vault_authority(
+            program_id,
+            vault_authority_info,
+            vesting_state_info,
+            &vesting_state,
+        )?;
+        if *funder_info.key != vesting_state.funder {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *vault_info.key != vesting_state.vault {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Skip schedules with something left to claim or a quota slot to release
+        if vesting_state.claimed_amount != vesting_state.amount
+            || vesting_state.quota_owner != Pubkey::default()
+        {
+            continue;
+        }
+
+        // Close the vault if it is still open, skipping schedules whose vault holds tokens
+        if vault_info.data_len() > 0 {
+            let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+            if vault.owner != *vault_authority_info.key {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            if vault.amount > 0 {
+                continue;
+            }
+            close_empty_vault(
+                vesting_state_info,
+                &vesting_state,
+                vault_info,
+                funder_info,
+                vault_authority_info,
+                token_program_info,
+            )?;
+        }
+
+        close_vesting_state(vesting_state_info, funder_info)?;
+        closed += 1;
+    }
+    msg!(
+        "Closed {} of {} schedules",
+        closed,
+        schedule_infos.len() / 3
+    );
+
+    Ok(())
+}
+
+/**
+ * Closes an empty vault, returning its rent to the funder.
+ *
+ * Parameters:
+ * - `vesting_state_info`: The vesting state account the vault belongs to.
+ * - `vesting_state`: The vesting state.
+ * - `vault_info`: The empty vault account.
+ * - `funder_info`: The funder's account, which receives the vault rent.
+ * - `vault_authority_info`: The vault authority PDA, which signs the close.
+ * - `token_program_info`: The SPL token program account.
+ */
+fn close_empty_vault<'a>(
+    vesting_state_info: &AccountInfo<'a>,
+    vesting_state: &VestingState,
+    vault_info: &AccountInfo<'a>,
+    funder_info: &AccountInfo<'a>,
+    vault_authority_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let bump = [vesting_state.vault_authority_bump];
+    let vault_signer_seeds = vault_authority_seeds(vesting_state_info.key, &bump);
+    let close_ix = spl_token::instruction::close_account(
+        token_program_info.key,
+        vault_info.key,
+        funder_info.key,
+        vault_authority_info.key,
+        &[],
+    )?;
+    invoke_signed(
+        &close_ix,
+        &[
+            vault_info.clone(),
+            funder_info.clone(),
+            vault_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&vault_signer_seeds],
+    )
+}
+
+/**
+ * Reclaims the unclaimed tokens of a schedule whose claim window has expired.
+ *
+ * This function lets the funder take back everything the recipient has not claimed by
+ * `claim_expiry`, vested or not. The schedule is closed out like a final claim, leaving any
+ * residual vault balance for `close_vault`. The tokens go to the same destination as revoked
+ * tokens, see `revoke_refund_owner`.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[writable]` The vault account holding the unclaimed tokens.
+ * 2. `[signer]` The funder of the schedule.
+ * 3. `[writable]` The token account receiving the unclaimed tokens.
+ * 4. `[]` The vault authority PDA, which signs the transfer out of the vault.
+ * 5. `[]` The token mint recorded at init.
+ * 6. `[]` The SPL token program account.
+ * 7. `[]` The Clock sysvar.
+ */
+pub fn reclaim_expired(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let funder_token_info = next_account_info(account_info_iter)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    check_vault_authority(
+        program_id,
+        vault_authority_info,
+        vesting_state_info,
+        &vesting_state,
+    )?;
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !is_claim_expired(&vesting_state, clock.unix_timestamp) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate the unclaimed tokens go back to the refund destination in the schedule mint
+    check_distinct_accounts(&[vesting_state_info, vault_info, funder_token_info])?;
+    if *mint_info.key != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let funder_token = spl_token::state::Account::unpack(&funder_token_info.try_borrow_data()?)?;
+    if funder_token.owner != revoke_refund_owner(&vesting_state) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+    if vault.mint != vesting_state.mint || funder_token.mint != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let unclaimed = vesting_state.amount - vesting_state.claimed_amount;
+    vesting_state.amount = vesting_state.claimed_amount;
+    vesting_state.is_initialized = false;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    let bump = [vesting_state.vault_authority_bump];
+    let vault_signer_seeds = vault_authority_seeds(vesting_state_info.key, &bump);
+    let transfer_ix = spl_token::instruction::transfer_checked(
+        token_program_info.key,
+        vault_info.key,
+        mint_info.key,
+        funder_token_info.key,
+        vault_authority_info.key,
+        &[],
+        unclaimed,
+        vesting_state.decimals,
+    )?;
+    invoke_signed(
+        &transfer_ix,
+        &[
+            vault_info.clone(),
+            mint_info.clone(),
+            funder_token_info.clone(),
+            vault_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&vault_signer_seeds],
+    )?;
+    msg!("Reclaimed {} unclaimed tokens", unclaimed);
+
+    Ok(())
+}
+
+/**
+ * Relinquishes a vesting schedule.
+ *
+ * This function lets the recipient voluntarily return their grant, giving back everything they
+ * have not claimed yet, vested or not. The schedule is closed out like a final claim, leaving
+ * any residual vault balance for `close_vault`. The tokens go to the same destination as
+ * revoked tokens, see `revoke_refund_owner`.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[writable]` The vault account holding the unclaimed tokens.
+ * 2. `[signer]` The recipient of the schedule.
+ * 3. `[writable]` The token account receiving the unclaimed tokens.
+ * 4. `[]` The vault authority PDA, which signs the transfer out of the vault.
+ * 5. `[]` The token mint recorded at init.
+ * 6. `[]` The SPL token program account.
+ */
+pub fn relinquish(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let recipient_info = next_account_info(account_info_iter)?;
+    let funder_token_info = next_account_info(account_info_iter)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    check_vault_authority(
+        program_id,
+        vault_authority_info,
+        vesting_state_info,
+        &vesting_state,
+    )?;
+    if !recipient_info.is_signer || *recipient_info.key != vesting_state.receiver {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Validate the unclaimed tokens go back to the refund destination in the schedule mint
+    check_distinct_accounts(&[vesting_state_info, vault_info, funder_token_info])?;
+    if *mint_info.key != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let funder_token = spl_token::state::Account::unpack(&funder_token_info.try_borrow_data()?)?;
+    if funder_token.owner != revoke_refund_owner(&vesting_state) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+    if vault.mint != vesting_state.mint || funder_token.mint != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let unclaimed = vesting_state.amount - vesting_state.claimed_amount;
+    vesting_state.amount = vesting_state.claimed_amount;
+    vesting_state.is_initialized = false;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    let bump = [vesting_state.vault_authority_bump];
+    let vault_signer_seeds = vault_authority_seeds(vesting_state_info.key, &bump);
+    let transfer_ix = spl_token::instruction::transfer_checked(
+        token_program_info.key,
+        vault_info.key,
+        mint_info.key,
+        funder_token_info.key,
+        vault_authority_info.key,
+        &[],
+        unclaimed,
+        vesting_state.decimals,
+    )?;
+    invoke_signed(
+        &transfer_ix,
+        &[
+            vault_info.clone(),
+            mint_info.clone(),
+            funder_token_info.clone(),
+            vault_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&vault_signer_seeds],
+    )?;
+    msg!("Relinquished {} unclaimed tokens", unclaimed);
+
+    Ok(())
+}
+
+/**
+ * Claims the rest of a vesting schedule early, forfeiting a penalty.
+ *
+ * This function lets the recipient of a schedule created with `allow_early_claim` take
+ * everything they have not claimed yet at once, vested or not. `early_claim_penalty_bps` of the
+ * unvested tokens is forfeited to the same destination as revoked tokens, the funder unless the
+ * schedule records a refund owner, see `early_claim_penalty` and `revoke_refund_owner`. The
+ * released tokens are then split like a regular claim, the claim fee first and withheld tax
+ * second, see `claim_split`. The schedule is closed out like a final claim, leaving any residual
+ * vault balance for `close_vault`, and the `ClaimOutcome` of the released tokens is set as
+ * return data.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[writable]` The vault account holding the unclaimed tokens.
+ * 2. `[signer]` The receiver of the schedule.
+ * 3. `[writable]` The recipient's token account, owned by the receiver, which will receive the
+ *    tokens.
+ * 4. `[writable]` The token account receiving the penalty, owned by the funder or, if the
+ *    schedule records one, the refund owner.
+ * 5. `[]` The vault authority PDA, which signs the transfers out of the vault.
+ * 6. `[]` The token mint recorded at init.
+ * 7. `[]` The SPL token program account.
+ * 8. `[]` The Clock sysvar.
+ * 9. `[]` The KYC attestation of the receiver, only if the schedule requires KYC, see
+ *   `check_kyc_attestation`.
+ * 10. `[writable]` The treasury token account, only if the schedule has a claim fee.
+ * 11. `[writable]` The tax account, only if the schedule withholds tax.
+ *
+ * Optional accounts that do not apply are left out, the following ones move up.
+ */
+pub fn claim_early(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let receiver_info = next_account_info(account_info_iter)?;
+    let recipient_info = next_account_info(account_info_iter)?;
+    let funder_token_info = next_account_info(account_info_iter)?;
+    let vault_authority_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if !vesting_state.allow_early_claim {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if !vesting_state.active {
+        return Err(VestingError::NotActive.into());
+    }
+    if is_claim_expired(&vesting_state, clock.unix_timestamp) {
+        return Err(VestingError::ClaimExpired.into());
+    }
+    check_vault_authority(
+        program_id,
+        vault_authority_info,
+        vesting_state_info,
+        &vesting_state,
+    )?;
+    if !receiver_info.is_signer || *receiver_info.key != vesting_state.receiver {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Validate the released tokens go to the receiver and the penalty to the refund destination,
+    // all in the schedule mint
+    check_distinct_accounts(&[
+        vesting_state_info,
+        vault_info,
+        recipient_info,
+        funder_token_info,
+    ])?;
+    if *mint_info.key != vesting_state.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    if vault_info.owner != token_program_info.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let recipient_token = spl_token::state::Account::unpack(&recipient_info.try_borrow_data()?)?;
+    if recipient_token.owner != vesting_state.receiver {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if recipient_token.is_frozen() {
+        return Err(VestingError::RecipientFrozen.into());
+    }
+    let funder_token = spl_token::state::Account::unpack(&funder_token_info.try_borrow_data()?)?;
+    if funder_token.owner != revoke_refund_owner(&vesting_state) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let vault = spl_token::state::Account::unpack(&vault_info.try_borrow_data()?)?;
+    if vault.mint != vesting_state.mint
+        || recipient_token.mint != vesting_state.mint
+        || funder_token.mint != vesting_state.mint
+    {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    // Validate the receiver holds a current KYC attestation of the schedule's verifier
+    if vesting_state.kyc_verifier != Pubkey::default() {
+        let attestation_info = next_account_info(account_info_iter)?;
+        check_kyc_attestation(attestation_info, &vesting_state, clock.unix_timestamp)?;
+    }
+
+    // Validate the fee and tax destinations recorded at init
+    let treasury_info = if vesting_state.claim_fee_bps > 0 {
+        let treasury_info = next_account_info(account_info_iter)?;
+        if *treasury_info.key != vesting_state.treasury {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_distinct_accounts(&[vault_info, treasury_info])?;
+        Some(treasury_info)
+    } else {
+        None
+    };
+    let tax_info = if vesting_state.withhold_bps > 0 {
+        let tax_info = next_account_info(account_info_iter)?;
+        if *tax_info.key != vesting_state.tax_account {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_distinct_accounts(&[vault_info, tax_info])?;
+        Some(tax_info)
+    } else {
+        None
+    };
+
+    let unclaimed = vesting_state.amount - vesting_state.claimed_amount;
+    if unclaimed == 0 {
+        return Err(VestingError::NothingToClaim.into());
+    }
+    let penalty = early_claim_penalty(&vesting_state, clock.unix_timestamp)?;
+    let released = unclaimed - penalty;
+    let (fee, withheld, recipient_amount) = claim_split(&vesting_state, released)?;
+
+    // Close out the schedule, the forfeited penalty no longer counts towards its amount
+    vesting_state.amount -= penalty;
+    vesting_state.claimed_amount = vesting_state.amount;
+    vesting_state.last_claim_at = clock.unix_timestamp;
+    let all_milestones = milestone_mask(vesting_state.step_count);
+    set_milestones_claimed(&mut vesting_state, all_milestones);
+    vesting_state.is_initialized = false;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    let bump = [vesting_state.vault_authority_bump];
+    let vault_signer_seeds = vault_authority_seeds(vesting_state_info.key, &bump);
+
+    // Forfeit the penalty to the refund destination
+    if penalty > 0 {
+        let penalty_ix = spl_token::instruction::transfer_checked(
+            token_program_info.key,
+            vault_info.key,
+            mint_info.key,
+            funder_token_info.key,
+            vault_authority_info.key,
+            &[],
+            penalty,
+            vesting_state.decimals,
+        )?;
+        invoke_signed(
+            &penalty_ix,
+            &[
+                vault_info.clone(),
+                mint_info.clone(),
+                funder_token_info.clone(),
+                vault_authority_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&vault_signer_seeds],
+        )?;
+    }
+
+    // Route the claim fee to the treasury and withhold tax from the recipient's share
+    if let Some(treasury_info) = treasury_info.filter(|_| fee > 0) {
+        let fee_ix = spl_token::instruction::transfer_checked(
+            token_program_info.key,
+            vault_info.key,
+            mint_info.key,
+            treasury_info.key,
+            vault_authority_info.key,
+            &[],
+            fee,
+            vesting_state.decimals,
+        )?;
+        invoke_signed(
+            &fee_ix,
+            &[
+                vault_info.clone(),
+                mint_info.clone(),
+                treasury_info.clone(),
+                vault_authority_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&vault_signer_seeds],
+        )?;
+    }
+    if let Some(tax_info) = tax_info.filter(|_| withheld > 0) {
+        let tax_ix = spl_token::instruction::transfer_checked(
+            token_program_info.key,
+            vault_info.key,
+            mint_info.key,
+            tax_info.key,
+            vault_authority_info.key,
+            &[],
+            withheld,
+            vesting_state.decimals,
+        )?;
+        invoke_signed(
+            &tax_ix,
+            &[
+                vault_info.clone(),
+                mint_info.clone(),
+                tax_info.clone(),
+                vault_authority_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&vault_signer_seeds],
+        )?;
+    }
+
+    // Transfer the released tokens to the recipient, signed by the vault authority
+    let transfer_ix = spl_token::instruction::transfer_checked(
+        token_program_info.key,
+        vault_info.key,
+        mint_info.key,
+        recipient_info.key,
+        vault_authority_info.key,
+        &[],
+        recipient_amount,
+        vesting_state.decimals,
+    )?;
+    invoke_signed(
+        &transfer_ix,
+        &[
+            vault_info.clone(),
+            mint_info.clone(),
+            recipient_info.clone(),
+            vault_authority_info.clone(),
+            token_program_info.clone(),
+        ],
+        &[&vault_signer_seeds],
+    )?;
+    msg!(
+        "Claimed {} tokens early, forfeiting {} tokens",
+        released,
+        penalty
+    );
+
+    // Set return data last, CPIs reset it
+    let outcome = ClaimOutcome {
+        transferred: released,
+        total_claimed: vesting_state.claimed_amount,
+        is_final: true,
+    };
+    set_return_data(&outcome.to_bytes());
+
+    Ok(())
+}
+
+/**
+ * Activates a vesting schedule.
+ *
+ * This function lets the funder enable claims on a schedule created in advance, see
+ * `init_vesting`. Activation is final, a schedule cannot be deactivated again.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[signer]` The funder of the schedule.
+ */
+pub fn activate(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if vesting_state.active {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    vesting_state.active = true;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Pauses or resumes a vesting schedule.
+ *
+ * This function lets the funder stop the vesting clock of a linear or stepped schedule, see
+ * `vesting_clock`. Nothing vests while the schedule is paused, already vested tokens can still
+ * be claimed. On resume the paused interval is added to `paused_duration`, shifting the
+ * effective end of the schedule by its length.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[signer]` The funder of the schedule.
+ * 2. `[]` The Clock sysvar.
+ *
+ * Parameters:
+ * - `paused`: Whether to pause (`true`) or resume (`false`) the schedule.
+ */
+pub fn set_paused(program_id: &Pubkey, accounts: &[AccountInfo], paused: bool) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Drip schedules have no end to extend
+    if vesting_state.rate_per_second > 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let now = clock.unix_timestamp;
+    let is_paused = vesting_state.paused_at != 0;
+    if paused == is_paused {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if paused {
+        vesting_state.paused_at = now;
+    } else {
+        let paused_for = now
+            .checked_sub(vesting_state.paused_at)
+            .ok_or(VestingError::MathOverflow)?;
+        vesting_state.paused_duration = vesting_state
+            .paused_duration
+            .checked_add(paused_for)
+            .ok_or(VestingError::MathOverflow)?;
+        vesting_state.paused_at = 0;
+    }
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Freezes or thaws a vesting schedule.
+ *
+ * This function lets the funder halt every operation on a schedule, including claims, without
+ * changing its timing: tokens keep vesting while the schedule is frozen and become claimable
+ * once it is thawed, unlike `set_paused` which stops the vesting clock.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[signer]` The funder of the schedule.
+ *
+ * Parameters:
+ * - `frozen`: Whether to freeze (`true`) or thaw (`false`) the schedule.
+ */
+pub fn set_frozen(program_id: &Pubkey, accounts: &[AccountInfo], frozen: bool) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if vesting_state.is_frozen == frozen {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    vesting_state.is_frozen = frozen;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Extends a linear vesting schedule to a later end.
+ *
+ * This function lets the funder push out `vesting_end`. The linear stream is recomputed from the
+ * start of the curve (`checkpoint_at`, the original start unless the schedule was split or
+ * revoked) to the new end, so the remainder vests at a slower rate. Already claimed tokens are
+ * honored, never clawed back: if the recomputed curve falls behind `claimed_amount`, nothing is
+ * claimable until it catches up again. Irrevocable schedules cannot be extended.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[signer]` The funder of the schedule.
+ *
+ * Parameters:
+ * - `new_end`: The new end of the schedule on the vesting clock, after the current end.
+ */
+pub fn extend_vesting(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_end: i64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if vesting_state.irrevocable {
+        return Err(VestingError::Irrevocable.into());
+    }
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Drip schedules have no end, moving the end of a stepped schedule would move milestones
+    // that were already claimed and the end of a periodic or curve schedule follows from its
+    // periods or control points
+    if vesting_state.rate_per_second > 0
+        || vesting_state.step_count > 0
+        || vesting_state.period_seconds > 0
+        || vesting_state.curve_point_count > 0
+        || new_end <= vesting_state.vesting_end
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Validate the end stays representable once shifted by the time paused so far
+    new_end
+        .checked_add(vesting_state.paused_duration)
+        .ok_or(VestingError::MathOverflow)?;
+
+    vesting_state.vesting_end = new_end;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Proposes a new funder for a vesting schedule.
+ *
+ * This function lets the funder start handing over its role, e.g. the authority to revoke,
+ * pause and freeze the schedule. The new funder only takes over once it calls
+ * `accept_funder`, proposing the current funder again cancels a pending handover. Revoked
+ * tokens keep going to the outgoing funder after the handover unless a refund owner is passed,
+ * see `revoke_refund_owner`.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[signer]` The funder of the schedule.
+ * 2. `[]` The proposed new funder.
+ * 3. `[]` The owner of the token accounts receiving revoked tokens from now on, optional.
+ */
+pub fn set_funder(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let new_funder_info = next_account_info(account_info_iter)?;
+    let refund_owner_info = next_account_info(account_info_iter).ok();
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    vesting_state.pending_funder = if *new_funder_info.key == vesting_state.funder {
+        Pubkey::default()
+    } else {
+        *new_funder_info.key
+    };
+    if let Some(refund_owner_info) = refund_owner_info {
+        vesting_state.refund_owner = *refund_owner_info.key;
+    }
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Accepts the funder role of a vesting schedule.
+ *
+ * This function completes a handover started with `set_funder`, making the proposed funder the
+ * funder of the schedule. If no refund owner is recorded yet, the outgoing funder becomes the
+ * refund owner so revoked tokens keep going where they went before.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[signer]` The proposed new funder.
+ */
+pub fn accept_funder(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let new_funder_info = next_account_info(account_info_iter)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if vesting_state.is_frozen {
+        return Err(VestingError::ScheduleFrozen.into());
+    }
+    if vesting_state.pending_funder == Pubkey::default()
+        || !new_funder_info.is_signer
+        || *new_funder_info.key != vesting_state.pending_funder
+    {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    vesting_state.refund_owner = revoke_refund_owner(&vesting_state);
+    vesting_state.funder = vesting_state.pending_funder;
+    vesting_state.pending_funder = Pubkey::default();
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Tops up the lamports of a vesting state account to the rent-exempt minimum.
+ *
+ * This function restores the rent exemption of a vesting state account that fell below the
+ * rent-exempt minimum, e.g. after the cluster's rent parameters changed. The payer covers the
+ * shortfall, accounts that are already rent-exempt are left untouched.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[writable, signer]` The payer of the shortfall.
+ * 2. `[]` The system program.
+ * 3. `[]` The Rent sysvar.
+ */
+pub fn top_up_rent(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let shortfall = rent
+        .minimum_balance(vesting_state_info.data_len())
+        .saturating_sub(vesting_state_info.lamports());
+    if shortfall == 0 {
+        return Ok(());
+    }
+
+    let transfer_ix =
+        system_instruction::transfer(payer_info.key, vesting_state_info.key, shortfall);
+    invoke(
+        &transfer_ix,
+        &[
+            payer_info.clone(),
+            vesting_state_info.clone(),
+            system_program_info.clone(),
+        ],
+    )?;
+    msg!("Topped up {} lamports of rent", shortfall);
+
+    Ok(())
+}
+
+/**
+ * Shrinks a vesting state account to the current `VestingState` layout.
+ *
+ * This function reallocates a vesting state account that is larger than `VestingState::LEN`,
+ * e.g. one created for a larger layout before a migration dropped fields or over-allocated at
+ * init, and returns the rent freed by the shrink to the funder. Bytes past `VestingState::LEN`
+ * are dropped, the layout only ever reads the leading `VestingState::LEN` bytes. Accounts that
+ * already match the layout are left untouched.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[writable]` The funder of the schedule, which receives the freed rent.
+ * 2. `[]` The Rent sysvar.
+ */
+pub fn shrink_state(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let vesting_state = VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if vesting_state.funder == Pubkey::default() || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if vesting_state_info.data_len() <= VestingState::LEN {
+        return Ok(());
+    }
+
+    vesting_state_info.realloc(VestingState::LEN, false)?;
+    let refund = vesting_state_info
+        .lamports()
+        .saturating_sub(rent.minimum_balance(VestingState::LEN));
+    **vesting_state_info.try_borrow_mut_lamports()? -= refund;
+    **funder_info.try_borrow_mut_lamports()? = funder_info
+        .lamports()
+        .checked_add(refund)
+        .ok_or(VestingError::MathOverflow)?;
+    msg!("Shrunk vesting state, refunded {} lamports of rent", refund);
+
+    Ok(())
+}
+
+/**
+ * Initializes the launch of a launch authority.
+ *
+ * This function creates the launch PDA that schedules of a cohort reference to start vesting
+ * together, see `init_vesting`. The launch timestamp is set later by the authority with
+ * `set_launch`.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The launch PDA, `[LAUNCH_SEED, authority]`.
+ * 1. `[writable, signer]` The payer of the launch account rent.
+ * 2. `[]` The launch authority.
+ * 3. `[]` The system program.
+ * 4. `[]` The Rent sysvar.
+ */
+pub fn init_launch(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let launch_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (launch_address, bump) = find_launch_address(authority_info.key, program_id);
+    if *launch_info.key != launch_address {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if launch_info.data_len() > 0 {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Create the launch account, signed by the launch PDA
+    let create_ix = system_instruction::create_account(
+        payer_info.key,
+        launch_info.key,
+        rent.minimum_balance(LaunchState::LEN),
+        LaunchState::LEN as u64,
+        program_id,
+    );
+    invoke_signed(
+        &create_ix,
+        &[
+            payer_info.clone(),
+            launch_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[LAUNCH_SEED, authority_info.key.as_ref(), &[bump]]],
+    )?;
+
+    let launch = LaunchState {
+        is_initialized: true,
+        bump,
+        authority: *authority_info.key,
+        launch_at: 0,
+    };
+    launch.pack_into_slice(&mut launch_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Sets the launch timestamp.
+ *
+ * This function lets the launch authority record when its cohort starts vesting. The timestamp
+ * is final once set, pending schedules referencing the launch can then be started with
+ * `apply_launch`.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The launch PDA.
+ * 1. `[signer]` The launch authority.
+ * 2. `[]` The Clock sysvar.
+ *
+ * Parameters:
+ * - `launch_at`: The Unix timestamp of the launch, 0 for now. It cannot lie in the past.
+ */
+pub fn set_launch(program_id: &Pubkey, accounts: &[AccountInfo], launch_at: i64) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let launch_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    let mut launch = load_launch(program_id, launch_info)?;
+    if !authority_info.is_signer || *authority_info.key != launch.authority {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if launch.launch_at != 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    launch.launch_at = if launch_at == 0 {
+        clock.unix_timestamp
+    } else if launch_at < clock.unix_timestamp {
+        return Err(ProgramError::InvalidArgument);
+    } else {
+        launch_at
+    };
+    launch.pack_into_slice(&mut launch_info.try_borrow_mut_data()?);
+    msg!("Launch set to {}", launch.launch_at);
+
+    Ok(())
+}
+
+/**
+ * Starts a pending schedule at the timestamp of its launch.
+ *
+ * This function can be called by anyone once the launch authority has set the launch
+ * timestamp. It sets `vesting_start` to the launch timestamp and `vesting_end` to
+ * `launch_duration` after it, see `is_launch_pending`.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The pending vesting state account.
+ * 1. `[]` The launch PDA referenced by the schedule.
+ */
+pub fn apply_launch(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let launch_info = next_account_info(account_info_iter)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !vesting_state.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if !is_launch_pending(&vesting_state) || *launch_info.key != vesting_state.launch {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let launch = load_launch(program_id, launch_info)?;
+    if launch.launch_at == 0 {
+        return Err(VestingError::LaunchPending.into());
+    }
+
+    vesting_state.vesting_start = launch.launch_at;
+    vesting_state.vesting_end = launch
+        .launch_at
+        .checked_add(vesting_state.launch_duration)
+        .ok_or(VestingError::MathOverflow)?;
+    vesting_state.last_claim_at = launch.launch_at;
+    vesting_state.checkpoint_at = launch.launch_at;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Overwrites the timeline and claimed amount of a schedule.
+ *
+ * This function only exists with the `test-utils` feature and must never be deployed. It lets
+ * integration tests put a schedule into an edge-case state, e.g. half way through its stream,
+ * without warping the clock through the whole setup. No other field is touched and nothing is
+ * validated beyond the funder's signature.
+ *
+ * Accounts expected by this instruction:
+ * 0. `[writable]` The vesting state account.
+ * 1. `[signer]` The funder of the schedule.
+ *
+ * Parameters:
+ * - `params.vesting_start`: The new `vesting_start`, also used as `checkpoint_at`.
+ * - `params.vesting_end`: The new `vesting_end`.
+ * - `params.claimed_amount`: The new `claimed_amount`.
+ */
+#[cfg(feature = "test-utils")]
+pub fn set_test_state(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: TestStateParams,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_state_info = next_account_info(account_info_iter)?;
+    let funder_info = next_account_info(account_info_iter)?;
+
+    if vesting_state_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut vesting_state =
+        VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+    if !funder_info.is_signer || *funder_info.key != vesting_state.funder {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    msg!("Overwriting vesting state for testing");
+    vesting_state.vesting_start = params.vesting_start;
+    vesting_state.vesting_end = params.vesting_end;
+    vesting_state.checkpoint_at = params.vesting_start;
+    vesting_state.claimed_amount = params.claimed_amount;
+    vesting_state.pack_into_slice(&mut vesting_state_info.try_borrow_mut_data()?);
+
+    Ok(())
+}
+
+/**
+ * Processes instructions for the smart contract.
+ *
+ * This function is the main entry point for the program. It dispatches calls to the appropriate
+ * function based on the instruction data. The first byte of the instruction data specifies the
+ * instruction to be executed, the second byte the version of the payload layout that follows.
+ * Only `INSTRUCTION_VERSION` is supported, other versions are rejected so future payload layouts
+ * can be introduced next to the current one.
+ *
+ * Parameters:
+ * - `_program_id`: The program ID.
+ * - `_accounts`: The accounts required for the instruction.
+ * - `_instruction_data`: The instruction data.
+ *
+ * Supported instructions:
+ * - `0`: Initialize vesting (calls `init_vesting`).
+ * - `1`: Claim vesting, with an optional memo (calls `claim_vesting`).
+ * - `2`: Split vesting (calls `split_vesting`).
+ * - `3`: Merge vesting (calls `merge_vesting`).
+ * - `4`: Request recovery (calls `request_recovery`).
+ * - `5`: Recover (calls `recover`).
+ * - `6`: Read claimable amounts (calls `read_claimable_amounts`).
+ * - `7`: Revoke part of the unvested tokens (calls `revoke_partial`).
+ * - `8`: Initialize the program config (calls `init_config`).
+ * - `9`: Update the program config (calls `update_config`).
+ * - `10`: Claim a single milestone, with an optional memo (calls `claim_milestone`).
+ * - `11`: Close the vault of a fully claimed schedule (calls `close_vault`).
+ * - `12`: Pause or resume a schedule (calls `set_paused`).
+ * - `13`: Freeze or thaw a schedule (calls `set_frozen`).
+ * - `14`: Read the vesting curve as sampled points (calls `read_vesting_curve`).
+ * - `15`: Top up a vesting state account to the rent-exempt minimum (calls `top_up_rent`).
+ * - `16`: Read whether a schedule has anything to claim (calls `read_is_claimable`).
+ * - `17`: Propose a new funder (calls `set_funder`).
+ * - `18`: Accept the funder role (calls `accept_funder`).
+ * - `19`: Extend a linear schedule to a later end (calls `extend_vesting`).
+ * - `20`: Close a schedule without pending obligations (calls `close_schedule`).
+ * - `21`: Activate a schedule (calls `activate`).
+ * - `22`: Top up a schedule with more tokens (calls `top_up_vesting`).
+ * - `23`: Read the pause-aware end of a schedule (calls `read_effective_end`).
+ * - `24`: Reclaim the unclaimed tokens of an expired schedule (calls `reclaim_expired`).
+ * - `25`: Initialize a plain linear schedule (calls `init_vesting` with defaults).
+ * - `26`: Initialize the funder quota of a funder (calls `init_funder_quota`).
+ * - `27`: Return the unclaimed tokens of a schedule to its funder (calls `relinquish`).
+ * - `28`: Close many fully claimed schedules (calls `close_many`).
+ * - `29`: Shrink a vesting state account to the current layout (calls `shrink_state`).
+ * - `30`: Initialize vesting, succeeding if already initialized identically (calls
+ *   `init_idempotent`).
+ * - `31`: Initialize the launch of a launch authority (calls `init_launch`).
+ * - `32`: Set the launch timestamp (calls `set_launch`).
+ * - `33`: Start a pending schedule at its launch (calls `apply_launch`).
+ * - `34`: Read the claimable amount before and after fees (calls `read_claim_preview`).
+ * - `35`: Claim on behalf of the receiver into its associated token account (calls
+ *   `crank_claim`).
+ * - `36`: Overwrite the timeline of a schedule, only with the `test-utils` feature (calls
+ *   `set_test_state`).
+ * - `37`: Read the milestones of a stepped schedule (calls `read_milestones`).
+ * - `38`: Rescue tokens sent to the vault authority of a schedule (calls `rescue_tokens`).
+ * - `39`: Claim the rest of a schedule early, forfeiting a penalty (calls `claim_early`).
+ * - `40`: Initialize the claim ledger of a schedule (calls `init_claim_ledger`).
+ *
+ * The payload length of every instruction is validated up front, see `check_payload_len`.
+ *
+ * Every instruction that changes a single schedule ends by setting its `InstructionStatus` as
+ * return data, see `set_status_return_data`. The following instructions set no status:
+ * - The read-only instructions `6`, `14`, `16`, `23`, `34` and `37`, which keep their own
+ *   return data.
+ * - The configuration instructions `8` and `9`, which change the program config.
+ * - `26` and `40`, which initialize a funder quota or a claim ledger rather than a schedule.
+ * - `28`, which closes many schedules at once.
+ * - `31` and `32`, which act on a launch rather than a schedule.
+ * - `38`, which moves stray tokens without changing the schedule.
+ *
+ * With the `profiling` feature the remaining compute units are logged before and after the
+ * handler runs, to profile the cost of each instruction during development.
+ */
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let (&instruction, versioned_payload) = instruction_data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let (&version, payload) = versioned_payload
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if version != INSTRUCTION_VERSION {
+        msg!("Unsupported instruction version {}", version);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    check_payload_len(instruction, payload)?;
+
+    #[cfg(feature = "profiling")]
+    msg!(
+        "Instruction {} start: {} compute units remaining",
+        instruction,
+        sol_remaining_compute_units()
+    );
+
+    let result = match instruction {
+        0 => {
+            let params = unpack_init_instruction(payload)?;
+            init_vesting(program_id, accounts, params)
+        }
+        1 => {
+            let memo = unpack_memo(payload)?;
+            claim_vesting(program_id, accounts, memo)
+        }
+        2 => {
+            let split_amount = unpack_amount_instruction(payload)?;
+            split_vesting(program_id, accounts, split_amount)
+        }
+        3 => merge_vesting(program_id, accounts),
+        4 => request_recovery(program_id, accounts),
+        5 => recover(program_id, accounts),
+        6 => read_claimable_amounts(program_id, accounts),
+        7 => {
+            let revoke_amount = unpack_amount_instruction(payload)?;
+            revoke_partial(program_id, accounts, revoke_amount)
+        }
+        8 => {
+            let params = unpack_config_instruction(payload)?;
+            init_config(program_id, accounts, params)
+        }
+        9 => {
+            let params = unpack_config_instruction(payload)?;
+            update_config(program_id, accounts, params)
+        }
+        10 => {
+            let (&index, memo) = payload
+                .split_first()
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            claim_milestone(program_id, accounts, index, unpack_memo(memo)?)
+        }
+        11 => close_vault(program_id, accounts),
+        12 => {
+            let paused = unpack_flag_instruction(payload)?;
+            set_paused(program_id, accounts, paused)
+        }
+        13 => {
+            let frozen = unpack_flag_instruction(payload)?;
+            set_frozen(program_id, accounts, frozen)
+        }
+        14 => {
+            let params = unpack_curve_instruction(payload)?;
+            read_vesting_curve(program_id, accounts, params)
+        }
+        15 => top_up_rent(program_id, accounts),
+        16 => read_is_claimable(program_id, accounts),
+        17 => set_funder(program_id, accounts),
+        18 => accept_funder(program_id, accounts),
+        19 => {
+            let new_end = unpack_timestamp_instruction(payload)?;
+            extend_vesting(program_id, accounts, new_end)
+        }
+        20 => close_schedule(program_id, accounts),
+        21 => activate(program_id, accounts),
+        22 => {
+            let top_up_amount = unpack_amount_instruction(payload)?;
+            top_up_vesting(program_id, accounts, top_up_amount)
+        }
+        23 => read_effective_end(program_id, accounts),
+        24 => reclaim_expired(program_id, accounts),
+        25 => {
+            let params = unpack_simple_init_instruction(payload)?;
+            init_vesting(program_id, accounts, params)
+        }
+        26 => init_funder_quota(program_id, accounts),
+        27 => relinquish(program_id, accounts),
+        28 => close_many(program_id, accounts),
+        29 => shrink_state(program_id, accounts),
+        30 => {
+            let params = unpack_init_instruction(payload)?;
+            init_idempotent(program_id, accounts, params)
+        }
+        31 => init_launch(program_id, accounts),
+        32 => {
+            let launch_at = unpack_timestamp_instruction(payload)?;
+            set_launch(program_id, accounts, launch_at)
+        }
+        33 => apply_launch(program_id, accounts),
+        34 => read_claim_preview(program_id, accounts),
+        35 => crank_claim(program_id, accounts),
+        #[cfg(feature = "test-utils")]
+        36 => {
+            let params = unpack_test_state_instruction(payload)?;
+            set_test_state(program_id, accounts, params)
+        }
+        37 => {
+            let (&first, _) = payload
+                .split_first()
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            read_milestones(program_id, accounts, first)
+        }
+        38 => rescue_tokens(program_id, accounts),
+        39 => claim_early(program_id, accounts),
+        40 => init_claim_ledger(program_id, accounts),
+        _ => Err(ProgramError::InvalidInstructionData),
+    };
+    let result = result.and_then(|()| set_status_return_data(program_id, accounts, instruction));
+
+    #[cfg(feature = "profiling")]
+    msg!(
+        "Instruction {} end: {} compute units remaining",
+        instruction,
+        sol_remaining_compute_units()
+    );
+
+    result
+}
+
+/**
+ * Sets the status of the schedule changed by an instruction as return data.
+ *
+ * This helper function gives composing programs a consistent result for every instruction that
+ * changes a schedule: the encoded `InstructionStatus` of the vesting state the instruction acted
+ * on, the target for merges and the source for splits. A schedule whose account was closed
+ * reports no flags and zero amounts. Claims keep returning their `ClaimOutcome`, followed by the
+ * status, so the status is always the last 18 bytes of the return data. Read-only instructions
+ * and instructions that do not act on a single schedule keep their return data.
+ *
+ * Parameters:
+ * - `program_id`: The program ID.
+ * - `accounts`: The accounts of the instruction.
+ * - `instruction`: The instruction byte.
+ */
+fn set_status_return_data(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    instruction_data: &[u8],
+    instruction: u8,
 ) -> ProgramResult {
-    let instruction = instruction_data[0];
+    let index = match instruction {
+        35 => 1,
+        0..=5 | 7 | 10..=13 | 15 | 17..=22 | 24 | 25 | 27 | 29 | 30 | 33 | 36 | 39 => 0,
+        // The exclusions documented at `process_instruction`; a new tag must be added to one of
+        // the arms above or here.
+        6 | 8 | 9 | 14 | 16 | 23 | 26 | 28 | 31 | 32 | 34 | 37 | 38 | 40 => return Ok(()),
+        _ => return Ok(()),
+    };
+    let vesting_state_info = accounts
+        .get(index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
 
-    match instruction {
-        0 => {
-            let (amount, vesting_end) = unpack_init_instruction(instruction_data)?;
-            init_vesting(program_id, accounts, amount, vesting_end)
+    let mut status = InstructionStatus {
+        instruction,
+        claimed_amount: 0,
+        remaining: 0,
+        flags: 0,
+    };
+    if vesting_state_info.owner == program_id && vesting_state_info.data_len() >= VestingState::LEN
+    {
+        let vesting_state =
+            VestingState::unpack_from_slice(&vesting_state_info.try_borrow_data()?)?;
+        status.claimed_amount = vesting_state.claimed_amount;
+        status.remaining = vesting_state
+            .amount
+            .saturating_sub(vesting_state.claimed_amount);
+        for (is_set, flag) in [
+            (vesting_state.is_initialized, InstructionStatus::OPEN),
+            (vesting_state.active, InstructionStatus::ACTIVE),
+            (vesting_state.paused_at != 0, InstructionStatus::PAUSED),
+            (vesting_state.is_frozen, InstructionStatus::FROZEN),
+        ] {
+            if is_set {
+                status.flags |= flag;
+            }
         }
-        1 => claim_vesting(program_id, accounts),
-        _ => Err(ProgramError::InvalidInstructionData),
     }
+
+    let mut return_data = match instruction {
+        1 | 10 | 35 | 39 => get_return_data()
+            .filter(|(return_program_id, _)| return_program_id == program_id)
+            .map(|(_, data)| data)
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    return_data.extend_from_slice(&status.to_bytes());
+    set_return_data(&return_data);
+
+    Ok(())
+}
+
+/**
+ * Validates the payload length of an instruction.
+ *
+ * This helper function checks the instruction data (without the leading instruction and version
+ * bytes) against the length its instruction expects, before it is dispatched. Instructions without
+ * parameters expect no payload, init expects its fixed parameters and curve points followed by 8
+ * bytes per co-funder, see `unpack_init_instruction`. Unknown instructions are rejected.
+ *
+ * Parameters:
+ * - `instruction`: The instruction byte.
+ * - `payload`: The instruction data.
+ */
+fn check_payload_len(instruction: u8, payload: &[u8]) -> ProgramResult {
+    let is_valid = match instruction {
+        0 | 30 => init_co_funders_offset(payload)
+            .is_some_and(|co_funders_at| (payload.len() - co_funders_at) % 8 == 0),
+        2 | 7 | 19 | 22 | 32 => payload.len() == 8,
+        8 | 9 => payload.len() == 34 + MAX_ALLOWED_TOKEN_PROGRAMS * 32 + 54,
+        1 => payload.len() <= MAX_CLAIM_MEMO_LEN,
+        10 => (1..=1 + MAX_CLAIM_MEMO_LEN).contains(&payload.len()),
+        12 | 13 | 37 => payload.len() == 1,
+        14 => payload.len() == 17,
+        25 => payload.len() == 16,
+        #[cfg(feature = "test-utils")]
+        36 => payload.len() == 24,
+        3..=6 | 11 | 15..=18 | 20 | 21 | 23 | 24 | 26..=29 | 31 | 33..=35 | 38..=40 => {
+            payload.is_empty()
+        }
+        _ => false,
+    };
+    if !is_valid {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+/**
+ * Locates the co-funder amounts of initialization instruction data.
+ *
+ * Parameters:
+ * - `data`: The instruction data, without the leading instruction and version bytes.
+ *
+ * Returns:
+ * - The offset of the first co-funder amount, after the curve points, or `None` if the data is
+ *   too short for its fixed fields and curve points.
+ */
+fn init_co_funders_offset(data: &[u8]) -> Option<usize> {
+    let curve_point_count = *data.get(241)? as usize;
+    let co_funders_at = 242 + curve_point_count * 16;
+    (data.len() >= co_funders_at).then_some(co_funders_at)
 }
 
 /**
  * Unpacks initialization instruction data.
  *
- * This helper function unpacks the amount and vesting end timestamp from the provided
- * instruction data. It expects the data to be exactly 16 bytes long: 8 bytes for the amount
- * and 8 bytes for the vesting end timestamp.
+ * This helper function unpacks the init parameters from the provided instruction data (without the
+ * leading instruction and version bytes). It expects the data to be at least 242 bytes long: 8
+ * bytes for the amount, 8 bytes for the vesting end timestamp, 2 bytes for the TGE share in basis
+ * points, 32 bytes for the recovery authority, 8 bytes for the drip rate per second, 32 bytes for
+ * the claim hook, 1 byte for the step count, 8 bytes for the unit size and 1 byte, either 0 or 1,
+ * for whether the amount is given in basis points of the funder's balance, 1 byte, either 0 or 1,
+ * for dual control, 1 byte, either 0 or 1, for whether the vesting end is given as a duration, 1
+ * byte, either 0 or 1, for irrevocability, 1 byte for the number of multisig signers of the funder,
+ * 1 byte, either 0 or 1, for NFT mode, 8 bytes for the claim expiry timestamp, 8 bytes for the
+ * unlock period in seconds, 8 bytes for the amount unlocked per period, 1 byte, either 0 or 1, for
+ * whether claims require KYC, 8 bytes for the vesting start timestamp, 1 byte, either 0 or 1, for
+ * whether the recipient locks after the first claim, 32 bytes for the launch, 1 byte, either 0 or
+ * 1, for whether claims can be cranked, 2 bytes for the withheld tax share in basis points, 32
+ * bytes for the tax account, 1 byte, either 0 or 1, for whether early claims are allowed, 2 bytes
+ * for the early claim penalty in basis points, 32 bytes for the claim governance program and 1 byte
+ * for the number of curve points, followed by 16 bytes for each curve point: 8 bytes for its
+ * timestamp and 8 bytes for its cumulative amount. It may be followed by 8 bytes for the amount of
+ * each co-funder.
+ *
+ * Parameters:
+ * - `data`: The instruction data.
+ *
+ * Returns:
+ * - The init parameters.
+ */
+fn unpack_init_instruction(data: &[u8]) -> Result<InitVestingParams, ProgramError> {
+    let co_funders_at = init_co_funders_offset(data).ok_or(ProgramError::InvalidInstructionData)?;
+    if (data.len() - co_funders_at) % 8 != 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount_in_bps = unpack_flag_instruction(&data[99..100])?;
+    let dual_control = unpack_flag_instruction(&data[100..101])?;
+    let end_is_duration = unpack_flag_instruction(&data[101..102])?;
+    let irrevocable = unpack_flag_instruction(&data[102..103])?;
+    let nft = unpack_flag_instruction(&data[104..105])?;
+    let kyc_required = unpack_flag_instruction(&data[129..130])?;
+    let lock_after_first_claim = unpack_flag_instruction(&data[138..139])?;
+    let auto_claim = unpack_flag_instruction(&data[171..172])?;
+    let allow_early_claim = unpack_flag_instruction(&data[206..207])?;
+    let recovery_authority_bytes: [u8; 32] = data[18..50].try_into().unwrap();
+    let claim_hook_bytes: [u8; 32] = data[58..90].try_into().unwrap();
+    let launch_bytes: [u8; 32] = data[139..171].try_into().unwrap();
+    let tax_account_bytes: [u8; 32] = data[174..206].try_into().unwrap();
+    let claim_governance_bytes: [u8; 32] = data[209..241].try_into().unwrap();
+    Ok(InitVestingParams {
+        amount: u64::from_le_bytes(data[0..8].try_into().unwrap()),
+        vesting_end: i64::from_le_bytes(data[8..16].try_into().unwrap()),
+        tge_bps: u16::from_le_bytes(data[16..18].try_into().unwrap()),
+        recovery_authority: Pubkey::from(recovery_authority_bytes),
+        rate_per_second: u64::from_le_bytes(data[50..58].try_into().unwrap()),
+        claim_hook: Pubkey::from(claim_hook_bytes),
+        step_count: data[90],
+        unit_size: u64::from_le_bytes(data[91..99].try_into().unwrap()),
+        amount_in_bps,
+        dual_control,
+        end_is_duration,
+        irrevocable,
+        multisig_signer_count: data[103],
+        nft,
+        claim_expiry: i64::from_le_bytes(data[105..113].try_into().unwrap()),
+        period_seconds: i64::from_le_bytes(data[113..121].try_into().unwrap()),
+        per_period: u64::from_le_bytes(data[121..129].try_into().unwrap()),
+        kyc_required,
+        vesting_start: i64::from_le_bytes(data[130..138].try_into().unwrap()),
+        lock_after_first_claim,
+        launch: Pubkey::from(launch_bytes),
+        auto_claim,
+        withhold_bps: u16::from_le_bytes(data[172..174].try_into().unwrap()),
+        tax_account: Pubkey::from(tax_account_bytes),
+        allow_early_claim,
+        early_claim_penalty_bps: u16::from_le_bytes(data[207..209].try_into().unwrap()),
+        claim_governance: Pubkey::from(claim_governance_bytes),
+        curve_points: data[242..co_funders_at]
+            .chunks_exact(16)
+            .map(|bytes| {
+                (
+                    i64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+                    u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+                )
+            })
+            .collect(),
+        co_funder_amounts: data[co_funders_at..]
+            .chunks_exact(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect(),
+    })
+}
+
+/**
+ * Unpacks simple initialization instruction data.
+ *
+ * This helper function unpacks the init parameters of a plain linear schedule from the provided
+ * instruction data (without the leading instruction and version bytes), for the common case that
+ * needs none of the optional features. It expects the data to be exactly 16 bytes long: 8 bytes for
+ * the amount and 8 bytes for the vesting end timestamp. All other parameters take their defaults:
+ * no TGE portion, no recovery authority, no claim hook, no expiry, revocable and funded by the
+ * funder alone.
  *
  * Parameters:
  * - `data`: The instruction data.
  *
  * Returns:
- * - A tuple containing the amount and the vesting end timestamp.
+ * - The init parameters.
  */
-fn unpack_init_instruction(data: &[u8]) -> Result<(u64, i64), ProgramError> {
+fn unpack_simple_init_instruction(data: &[u8]) -> Result<InitVestingParams, ProgramError> {
     if data.len() != 16 {
         return Err(ProgramError::InvalidInstructionData);
     }
-    let amount = u64::from_le_bytes(data[0..8].try_into().unwrap());
-    let vesting_end = i64::from_le_bytes(data[8..16].try_into().unwrap());
-    Ok((amount, vesting_end))
+    Ok(InitVestingParams {
+        amount: u64::from_le_bytes(data[0..8].try_into().unwrap()),
+        vesting_end: i64::from_le_bytes(data[8..16].try_into().unwrap()),
+        tge_bps: 0,
+        recovery_authority: Pubkey::default(),
+        rate_per_second: 0,
+        claim_hook: Pubkey::default(),
+        step_count: 0,
+        unit_size: 0,
+        amount_in_bps: false,
+        dual_control: false,
+        end_is_duration: false,
+        irrevocable: false,
+        multisig_signer_count: 0,
+        nft: false,
+        claim_expiry: 0,
+        period_seconds: 0,
+        per_period: 0,
+        kyc_required: false,
+        vesting_start: 0,
+        lock_after_first_claim: false,
+        launch: Pubkey::default(),
+        auto_claim: false,
+        withhold_bps: 0,
+        tax_account: Pubkey::default(),
+        allow_early_claim: false,
+        early_claim_penalty_bps: 0,
+        claim_governance: Pubkey::default(),
+        curve_points: Vec::new(),
+        co_funder_amounts: Vec::new(),
+    })
+}
+
+/**
+ * Unpacks instruction data carrying a single amount.
+ *
+ * This helper function unpacks an amount from the provided instruction data (without the leading
+ * instruction and version bytes). It expects the data to be exactly 8 bytes long.
+ *
+ * Parameters:
+ * - `data`: The instruction data.
+ *
+ * Returns:
+ * - The amount.
+ */
+fn unpack_amount_instruction(data: &[u8]) -> Result<u64, ProgramError> {
+    if data.len() != 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(u64::from_le_bytes(data[0..8].try_into().unwrap()))
+}
+
+/**
+ * Unpacks instruction data carrying a single timestamp.
+ *
+ * This helper function unpacks a timestamp from the provided instruction data (without the leading
+ * instruction and version bytes). It expects the data to be exactly 8 bytes long.
+ *
+ * Parameters:
+ * - `data`: The instruction data.
+ *
+ * Returns:
+ * - The timestamp.
+ */
+fn unpack_timestamp_instruction(data: &[u8]) -> Result<i64, ProgramError> {
+    if data.len() != 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(i64::from_le_bytes(data[0..8].try_into().unwrap()))
+}
+
+/**
+ * Unpacks the optional memo of a claim.
+ *
+ * This helper function unpacks the memo from the provided instruction data (without the leading
+ * instruction and version bytes and any fixed parameters). It expects the data to be at most
+ * `MAX_CLAIM_MEMO_LEN` bytes of UTF-8, empty data means no memo.
+ *
+ * Parameters:
+ * - `data`: The instruction data.
+ *
+ * Returns:
+ * - The memo, or `None` for no memo.
+ */
+fn unpack_memo(data: &[u8]) -> Result<Option<&str>, ProgramError> {
+    if data.len() > MAX_CLAIM_MEMO_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if data.is_empty() {
+        return Ok(None);
+    }
+    std::str::from_utf8(data)
+        .map(Some)
+        .map_err(|_| ProgramError::InvalidInstructionData)
+}
+
+/**
+ * Unpacks instruction data carrying a single flag.
+ *
+ * This helper function unpacks a boolean from the provided instruction data (without the leading
+ * instruction and version bytes). It expects the data to be exactly 1 byte long, either 0 or 1.
+ *
+ * Parameters:
+ * - `data`: The instruction data.
+ *
+ * Returns:
+ * - The flag.
+ */
+fn unpack_flag_instruction(data: &[u8]) -> Result<bool, ProgramError> {
+    match data {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/**
+ * Unpacks test state instruction data.
+ *
+ * This helper function unpacks the test state parameters from the provided instruction data
+ * (without the leading instruction and version bytes). It expects the data to be exactly 24 bytes
+ * long: 8 bytes for the vesting start timestamp, 8 bytes for the vesting end timestamp and
+ * 8 bytes for the claimed amount.
+ *
+ * Parameters:
+ * - `data`: The instruction data.
+ *
+ * Returns:
+ * - The test state parameters.
+ */
+#[cfg(feature = "test-utils")]
+fn unpack_test_state_instruction(data: &[u8]) -> Result<TestStateParams, ProgramError> {
+    if data.len() != 24 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(TestStateParams {
+        vesting_start: i64::from_le_bytes(data[0..8].try_into().unwrap()),
+        vesting_end: i64::from_le_bytes(data[8..16].try_into().unwrap()),
+        claimed_amount: u64::from_le_bytes(data[16..24].try_into().unwrap()),
+    })
+}
+
+/**
+ * Unpacks curve instruction data.
+ *
+ * This helper function unpacks the curve parameters from the provided instruction data (without the
+ * leading instruction and version bytes). It expects the data to be exactly 17 bytes long: 8 bytes
+ * for the start timestamp, 8 bytes for the end timestamp and 1 byte for the sample count.
+ *
+ * Parameters:
+ * - `data`: The instruction data.
+ *
+ * Returns:
+ * - The curve parameters.
+ */
+fn unpack_curve_instruction(data: &[u8]) -> Result<CurveParams, ProgramError> {
+    if data.len() != 17 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(CurveParams {
+        start: i64::from_le_bytes(data[0..8].try_into().unwrap()),
+        end: i64::from_le_bytes(data[8..16].try_into().unwrap()),
+        sample_count: data[16],
+    })
+}
+
+/**
+ * Unpacks config instruction data.
+ *
+ * This helper function unpacks the config parameters from the provided instruction data (without
+ * the leading instruction and version bytes). It expects the data to be exactly 152 bytes long: 2
+ * bytes for the claim fee in basis points, 32 bytes for the treasury, 32 bytes for each of the
+ * `MAX_ALLOWED_TOKEN_PROGRAMS` allowed token programs, 8 bytes for the minimum duration, 4 bytes
+ * for the per-funder schedule cap, 32 bytes for the KYC verifier, 1 byte, either 0 or 1, for
+ * whether vaults must be associated token accounts, 8 bytes for the maximum duration and 1 byte,
+ * either 0 or 1, for whether the program is deprecated.
+ *
+ * Parameters:
+ * - `data`: The instruction data.
+ *
+ * Returns:
+ * - The config parameters.
+ */
+fn unpack_config_instruction(data: &[u8]) -> Result<ConfigParams, ProgramError> {
+    let min_duration_offset = 34 + MAX_ALLOWED_TOKEN_PROGRAMS * 32;
+    let max_schedules_offset = min_duration_offset + 8;
+    let kyc_verifier_offset = max_schedules_offset + 4;
+    let require_ata_vault_offset = kyc_verifier_offset + 32;
+    let max_duration_offset = require_ata_vault_offset + 1;
+    let deprecated_offset = max_duration_offset + 8;
+    if data.len() != deprecated_offset + 1 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let deprecated = unpack_flag_instruction(&data[deprecated_offset..deprecated_offset + 1])?;
+    let require_ata_vault =
+        unpack_flag_instruction(&data[require_ata_vault_offset..require_ata_vault_offset + 1])?;
+    let kyc_verifier_bytes: [u8; 32] = data[kyc_verifier_offset..kyc_verifier_offset + 32]
+        .try_into()
+        .unwrap();
+    let treasury_bytes: [u8; 32] = data[2..34].try_into().unwrap();
+    let mut allowed_token_programs = [Pubkey::default(); MAX_ALLOWED_TOKEN_PROGRAMS];
+    for (i, token_program) in allowed_token_programs.iter_mut().enumerate() {
+        let offset = 34 + i * 32;
+        let token_program_bytes: [u8; 32] = data[offset..offset + 32].try_into().unwrap();
+        *token_program = Pubkey::from(token_program_bytes);
+    }
+    Ok(ConfigParams {
+        claim_fee_bps: u16::from_le_bytes(data[0..2].try_into().unwrap()),
+        treasury: Pubkey::from(treasury_bytes),
+        allowed_token_programs,
+        min_duration: i64::from_le_bytes(
+            data[min_duration_offset..min_duration_offset + 8]
+                .try_into()
+                .unwrap(),
+        ),
+        max_schedules_per_funder: u32::from_le_bytes(
+            data[max_schedules_offset..max_schedules_offset + 4]
+                .try_into()
+                .unwrap(),
+        ),
+        kyc_verifier: Pubkey::from(kyc_verifier_bytes),
+        require_ata_vault,
+        max_duration: i64::from_le_bytes(
+            data[max_duration_offset..max_duration_offset + 8]
+                .try_into()
+                .unwrap(),
+        ),
+        deprecated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A vesting state with every field zeroed.
+    fn blank_state() -> VestingState {
+        VestingState::unpack_from_slice(&[0u8; VestingState::LEN]).unwrap()
+    }
+
+    /// An initialized linear schedule of `amount` tokens vesting from 1000 to 2000.
+    fn linear_state(amount: u64) -> VestingState {
+        let mut state = blank_state();
+        state.is_initialized = true;
+        state.active = true;
+        state.amount = amount;
+        state.vesting_start = 1_000;
+        state.vesting_end = 2_000;
+        state.checkpoint_at = 1_000;
+        state
+    }
+
+    /// A stepped schedule of 800 tokens with 8 milestones, one every 125 seconds from 1000.
+    fn stepped_state() -> VestingState {
+        let mut state = linear_state(800);
+        state.step_count = 8;
+        state
+    }
+
+    #[test]
+    fn vested_amount_is_linear_and_inclusive_of_the_end() {
+        let state = linear_state(1_000);
+        assert_eq!(vested_amount(&state, 999), 0);
+        assert_eq!(vested_amount(&state, 1_000), 0);
+        assert_eq!(vested_amount(&state, 1_250), 250);
+        assert_eq!(vested_amount(&state, 1_999), 999);
+        assert_eq!(vested_amount(&state, 2_000), 1_000);
+        assert_eq!(vested_amount(&state, i64::MAX), 1_000);
+    }
+
+    #[test]
+    fn vested_amount_stops_while_paused() {
+        let mut state = linear_state(1_000);
+        state.paused_at = 1_500;
+        assert_eq!(vested_amount(&state, 1_800), 500);
+        state.paused_at = 0;
+        state.paused_duration = 300;
+        assert_eq!(vested_amount(&state, 1_800), 500);
+    }
+
+    #[test]
+    fn vested_bps_of_a_linear_schedule() {
+        let state = linear_state(1_000);
+        assert_eq!(vested_bps(&state, 1_000), 0);
+        assert_eq!(vested_bps(&state, 1_500), 5_000);
+        assert_eq!(vested_bps(&state, 2_000), 10_000);
+    }
+
+    #[test]
+    fn vested_bps_of_a_stepped_schedule() {
+        let state = stepped_state();
+        assert_eq!(vested_bps(&state, 1_124), 0);
+        assert_eq!(vested_bps(&state, 1_499), 3_750);
+        assert_eq!(vested_bps(&state, 1_500), 5_000);
+        assert_eq!(vested_bps(&state, 2_000), 10_000);
+    }
+
+    #[test]
+    fn seconds_until_next_vest_of_a_linear_schedule() {
+        let state = linear_state(1_000);
+        assert_eq!(seconds_until_next_vest(&state, 900), Some(100));
+        assert_eq!(seconds_until_next_vest(&state, 1_500), Some(0));
+        assert_eq!(seconds_until_next_vest(&state, 2_000), None);
+    }
+
+    #[test]
+    fn seconds_until_next_vest_of_a_stepped_schedule() {
+        let state = stepped_state();
+        assert_eq!(seconds_until_next_vest(&state, 1_000), Some(125));
+        assert_eq!(seconds_until_next_vest(&state, 1_130), Some(120));
+        assert_eq!(seconds_until_next_vest(&state, 1_875), Some(125));
+        assert_eq!(seconds_until_next_vest(&state, 2_000), None);
+    }
+
+    #[test]
+    fn seconds_until_next_vest_of_a_curve_on_a_flat_segment() {
+        let state = curve_state();
+        assert_eq!(seconds_until_next_vest(&state, 1_100), Some(100));
+        assert_eq!(seconds_until_next_vest(&state, 1_300), Some(0));
+    }
+
+    #[test]
+    fn seconds_until_next_vest_follows_the_paused_clock() {
+        // Paused before the start, the start moves out by the paused time
+        let mut state = linear_state(1_000);
+        state.paused_at = 900;
+        assert_eq!(seconds_until_next_vest(&state, 950), Some(100));
+        state.paused_at = 0;
+        state.paused_duration = 50;
+        assert_eq!(seconds_until_next_vest(&state, 1_000), Some(50));
+
+        let mut state = stepped_state();
+        state.paused_duration = 30;
+        assert_eq!(seconds_until_next_vest(&state, 1_130), Some(25));
+    }
+
+    #[test]
+    fn compute_claim_outcome_before_the_start() {
+        let state = linear_state(1_000);
+        let outcome = compute_claim_outcome(&state, 500).unwrap();
+        assert_eq!(
+            outcome,
+            ClaimOutcome {
+                transferred: 0,
+                total_claimed: 0,
+                is_final: false,
+            }
+        );
+    }
+
+    #[test]
+    fn compute_claim_outcome_half_way_after_a_claim() {
+        let mut state = linear_state(1_000);
+        state.claimed_amount = 300;
+        let outcome = compute_claim_outcome(&state, 1_500).unwrap();
+        assert_eq!(
+            outcome,
+            ClaimOutcome {
+                transferred: 200,
+                total_claimed: 500,
+                is_final: false,
+            }
+        );
+    }
+
+    #[test]
+    fn compute_claim_outcome_at_the_end_is_final() {
+        let mut state = linear_state(1_000);
+        state.claimed_amount = 400;
+        let outcome = compute_claim_outcome(&state, 2_000).unwrap();
+        assert_eq!(
+            outcome,
+            ClaimOutcome {
+                transferred: 600,
+                total_claimed: 1_000,
+                is_final: true,
+            }
+        );
+    }
+
+    #[test]
+    fn compute_claim_outcome_of_a_fully_claimed_schedule() {
+        let mut state = linear_state(1_000);
+        state.claimed_amount = 1_000;
+        let outcome = compute_claim_outcome(&state, 3_000).unwrap();
+        assert_eq!(outcome.transferred, 0);
+        assert!(outcome.is_final);
+    }
+
+    #[test]
+    fn claim_outcome_bytes() {
+        let outcome = ClaimOutcome {
+            transferred: 0x0102,
+            total_claimed: 0x0304,
+            is_final: true,
+        };
+        let mut expected = [0u8; 17];
+        expected[0] = 0x02;
+        expected[1] = 0x01;
+        expected[8] = 0x04;
+        expected[9] = 0x03;
+        expected[16] = 1;
+        assert_eq!(outcome.to_bytes(), expected);
+    }
+
+    /// A vesting state with every field set to a distinct value.
+    fn golden_state() -> VestingState {
+        let key = |byte: u8| Pubkey::new_from_array([byte; 32]);
+        let mut curve_points = [(0i64, 0u64); MAX_CURVE_POINTS];
+        curve_points[0] = (0x0a0b, 0x0c0d);
+        curve_points[MAX_CURVE_POINTS - 1] = (-2, u64::MAX);
+        VestingState {
+            is_initialized: true,
+            receiver: key(1),
+            funder: key(2),
+            amount: 0x0102_0304_0506_0708,
+            vesting_start: 0x11,
+            vesting_end: -0x12,
+            claimed_amount: 0x13,
+            tge_bps: 0x1415,
+            recovery_authority: key(3),
+            recovery_receiver: key(4),
+            recovery_requested_at: 0x16,
+            rate_per_second: 0x17,
+            last_claim_at: 0x18,
+            decimals: 9,
+            vault_authority_bump: 254,
+            claim_fee_bps: 0x191a,
+            treasury: key(5),
+            mint: key(6),
+            claim_hook: key(7),
+            checkpoint_at: 0x1b,
+            checkpoint_vested: 0x1c,
+            step_count: 12,
+            claimed_milestones: 0x8000_0000_0000_0001,
+            paused_at: 0x1d,
+            paused_duration: 0x1e,
+            is_frozen: true,
+            schedule_hash: Hash::new_from_array([8; 32]),
+            unit_size: 0x1f,
+            dual_control: true,
+            irrevocable: true,
+            pending_funder: key(9),
+            refund_owner: key(10),
+            active: true,
+            claim_expiry: 0x20,
+            quota_owner: key(11),
+            period_seconds: 0x21,
+            per_period: 0x22,
+            kyc_verifier: key(12),
+            lock_after_first_claim: true,
+            launch: key(13),
+            launch_duration: 0x23,
+            auto_claim: true,
+            curve_point_count: 2,
+            curve_points,
+            withhold_bps: 0x2425,
+            tax_account: key(14),
+            allow_early_claim: true,
+            early_claim_penalty_bps: 0x2627,
+            claim_governance: key(15),
+            vault: key(16),
+        }
+    }
+
+    /// The packed bytes of `golden_state`, written at the offsets of the documented layout.
+    fn golden_bytes() -> [u8; VestingState::LEN] {
+        let mut bytes = [0u8; VestingState::LEN];
+        let mut put = |offset: usize, value: &[u8]| {
+            bytes[offset..offset + value.len()].copy_from_slice(value)
+        };
+        put(0, &[1; 32]);
+        put(32, &[2; 32]);
+        put(64, &[8, 7, 6, 5, 4, 3, 2, 1]);
+        put(72, &[0x11, 0, 0, 0, 0, 0, 0, 0]);
+        put(80, &[0xee, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        put(88, &[1]);
+        put(89, &[0x13, 0, 0, 0, 0, 0, 0, 0]);
+        put(97, &[0x15, 0x14]);
+        put(99, &[3; 32]);
+        put(131, &[4; 32]);
+        put(163, &[0x16, 0, 0, 0, 0, 0, 0, 0]);
+        put(171, &[0x17, 0, 0, 0, 0, 0, 0, 0]);
+        put(179, &[0x18, 0, 0, 0, 0, 0, 0, 0]);
+        put(187, &[9]);
+        put(188, &[254]);
+        put(189, &[0x1a, 0x19]);
+        put(191, &[5; 32]);
+        put(223, &[6; 32]);
+        put(255, &[7; 32]);
+        put(287, &[0x1b, 0, 0, 0, 0, 0, 0, 0]);
+        put(295, &[0x1c, 0, 0, 0, 0, 0, 0, 0]);
+        put(303, &[12]);
+        put(304, &[1, 0, 0, 0, 0, 0, 0, 0x80]);
+        put(312, &[0x1d, 0, 0, 0, 0, 0, 0, 0]);
+        put(320, &[0x1e, 0, 0, 0, 0, 0, 0, 0]);
+        put(328, &[1]);
+        put(329, &[8; 32]);
+        put(361, &[0x1f, 0, 0, 0, 0, 0, 0, 0]);
+        put(369, &[1]);
+        put(370, &[1]);
+        put(371, &[9; 32]);
+        put(403, &[10; 32]);
+        put(435, &[1]);
+        put(436, &[0x20, 0, 0, 0, 0, 0, 0, 0]);
+        put(444, &[11; 32]);
+        put(476, &[0x21, 0, 0, 0, 0, 0, 0, 0]);
+        put(484, &[0x22, 0, 0, 0, 0, 0, 0, 0]);
+        put(492, &[12; 32]);
+        put(524, &[1]);
+        put(525, &[13; 32]);
+        put(557, &[0x23, 0, 0, 0, 0, 0, 0, 0]);
+        put(565, &[1]);
+        put(566, &[2]);
+        put(567, &[0x0b, 0x0a, 0, 0, 0, 0, 0, 0]);
+        put(575, &[0x0d, 0x0c, 0, 0, 0, 0, 0, 0]);
+        put(679, &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        put(687, &[0xff; 8]);
+        put(695, &[0x25, 0x24]);
+        put(697, &[14; 32]);
+        put(729, &[1]);
+        put(730, &[0x27, 0x26]);
+        put(732, &[15; 32]);
+        put(764, &[16; 32]);
+        bytes
+    }
+
+    #[test]
+    fn vesting_state_packs_to_the_golden_bytes() {
+        let mut packed = [0u8; VestingState::LEN];
+        golden_state().pack_into_slice(&mut packed);
+        assert_eq!(packed, golden_bytes());
+    }
+
+    #[test]
+    fn vesting_state_unpacks_from_the_golden_bytes() {
+        let state = VestingState::unpack_from_slice(&golden_bytes()).unwrap();
+        let mut repacked = [0u8; VestingState::LEN];
+        state.pack_into_slice(&mut repacked);
+        assert_eq!(repacked, golden_bytes());
+        assert_eq!(state.amount, 0x0102_0304_0506_0708);
+        assert_eq!(state.vesting_end, -0x12);
+        assert_eq!(state.receiver, Pubkey::new_from_array([1; 32]));
+        assert_eq!(state.claimed_milestones, 0x8000_0000_0000_0001);
+        assert_eq!(state.curve_points[MAX_CURVE_POINTS - 1], (-2, u64::MAX));
+        assert_eq!(state.claim_governance, Pubkey::new_from_array([15; 32]));
+    }
+
+    #[test]
+    fn simulate_claimable_from_packed_account_data() {
+        let mut state = linear_state(1_000);
+        state.claimed_amount = 100;
+        let mut data = vec![0u8; VestingState::LEN];
+        state.pack_into_slice(&mut data);
+        assert_eq!(simulate_claimable(&data, 1_500), Ok(400));
+        assert_eq!(simulate_claimable(&data, 2_500), Ok(900));
+
+        // Accounts grown past the current layout are read up to its length
+        data.resize(VestingState::LEN + 16, 0xff);
+        assert_eq!(simulate_claimable(&data, 1_500), Ok(400));
+    }
+
+    #[test]
+    fn simulate_claimable_of_closed_and_short_accounts() {
+        let mut state = linear_state(1_000);
+        state.is_initialized = false;
+        let mut data = [0u8; VestingState::LEN];
+        state.pack_into_slice(&mut data);
+        assert_eq!(simulate_claimable(&data, 1_500), Ok(0));
+        assert_eq!(
+            simulate_claimable(&data[..VestingState::LEN - 1], 1_500),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn schedule_hash_covers_the_terms_but_not_the_progress() {
+        let state = linear_state(1_000);
+        let hash = schedule_hash(&state);
+
+        let mut claimed = linear_state(1_000);
+        claimed.claimed_amount = 500;
+        claimed.last_claim_at = 1_500;
+        claimed.paused_at = 1_600;
+        assert_eq!(schedule_hash(&claimed), hash);
+
+        let mut longer = linear_state(1_000);
+        longer.vesting_end += 1;
+        assert_ne!(schedule_hash(&longer), hash);
+
+        let mut governed = linear_state(1_000);
+        governed.claim_governance = Pubkey::new_from_array([1; 32]);
+        assert_ne!(schedule_hash(&governed), hash);
+    }
+
+    #[test]
+    fn align_to_unit_rounds_intermediate_claims_down() {
+        let mut state = linear_state(1_000);
+        state.unit_size = 100;
+        assert_eq!(align_to_unit(&state, 0), 0);
+        assert_eq!(align_to_unit(&state, 199), 100);
+        assert_eq!(align_to_unit(&state, 200), 200);
+        assert_eq!(align_to_unit(&state, 1_000), 1_000);
+        assert_eq!(claimable_amount(&state, 1_250), 200);
+        assert_eq!(claimable_amount(&state, 2_000), 1_000);
+
+        state.unit_size = 0;
+        assert_eq!(align_to_unit(&state, 199), 199);
+    }
+
+    #[test]
+    fn align_to_unit_releases_the_remainder_at_the_end() {
+        let mut state = linear_state(1_050);
+        state.unit_size = 100;
+        state.claimed_amount = 1_000;
+        assert_eq!(align_to_unit(&state, 1_049), 1_000);
+        assert_eq!(claimable_amount(&state, 1_999), 0);
+        assert_eq!(claimable_amount(&state, 2_000), 50);
+    }
+
+    #[test]
+    fn milestone_bitmap_records_claims_in_any_order() {
+        let mut state = stepped_state();
+        for index in [1, 3, 4] {
+            let outcome = compute_milestone_claim_outcome(&state, 2_000, index).unwrap();
+            assert_eq!(outcome.transferred, 100);
+            set_milestones_claimed(&mut state, milestone_bit(index));
+            state.claimed_amount = outcome.total_claimed;
+        }
+        assert_eq!(state.claimed_milestones, 0b11010);
+        assert!(is_milestone_claimed(&state, 3));
+        assert!(!is_milestone_claimed(&state, 2));
+        assert_eq!(
+            compute_milestone_claim_outcome(&state, 2_000, 3),
+            Err(VestingError::MilestoneAlreadyClaimed.into())
+        );
+
+        set_milestones_claimed(&mut state, milestone_mask(7));
+        assert_eq!(state.claimed_milestones, 0b1111111);
+    }
+
+    #[test]
+    fn milestone_claims_wait_for_the_milestone() {
+        let state = stepped_state();
+        assert_eq!(milestone_unlock_at(&state, 0), 1_125);
+        assert_eq!(
+            compute_milestone_claim_outcome(&state, 1_124, 0),
+            Err(VestingError::MilestoneNotReached.into())
+        );
+        assert!(compute_milestone_claim_outcome(&state, 1_125, 0).is_ok());
+        assert_eq!(
+            compute_milestone_claim_outcome(&state, 2_000, 8),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn milestone_mask_covers_the_whole_bitmap() {
+        assert_eq!(milestone_mask(0), 0);
+        assert_eq!(milestone_mask(3), 0b111);
+        assert_eq!(milestone_mask(MAX_MILESTONES), u64::MAX);
+        assert_eq!(milestone_bit(MAX_MILESTONES - 1), 1 << 63);
+    }
+
+    #[test]
+    fn vesting_errors_convert_to_custom_program_errors() {
+        assert_eq!(
+            ProgramError::from(VestingError::NothingToClaim),
+            ProgramError::Custom(0)
+        );
+        assert_eq!(
+            ProgramError::from(VestingError::MathOverflow),
+            ProgramError::Custom(6)
+        );
+        assert_eq!(
+            ProgramError::from(VestingError::Deprecated),
+            ProgramError::Custom(26)
+        );
+        assert_eq!(
+            ProgramError::from(VestingError::GovernanceRequired),
+            ProgramError::Custom(27)
+        );
+    }
+
+    #[test]
+    fn vesting_result_propagates_into_program_results() {
+        fn overflowing() -> ProgramResult {
+            claim_fee(u64::MAX, u16::MAX)?;
+            Ok(())
+        }
+        assert_eq!(
+            claim_fee(u64::MAX, u16::MAX),
+            Err(VestingError::MathOverflow)
+        );
+        assert_eq!(overflowing(), Err(ProgramError::Custom(6)));
+    }
+
+    #[test]
+    fn unpack_rejects_a_one_byte_short_buffer() {
+        let mut data = [0u8; VestingState::LEN];
+        linear_state(1_000).pack_into_slice(&mut data);
+        assert_eq!(
+            VestingState::unpack_from_slice(&data[..VestingState::LEN - 1]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+        assert_eq!(
+            ClaimLedger::unpack_from_slice(&[0u8; ClaimLedger::LEN - 1]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+        assert_eq!(
+            ProgramConfig::unpack_from_slice(&[0u8; ProgramConfig::LEN - 1]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+
+    /// An initialized, empty claim ledger.
+    fn empty_ledger() -> ClaimLedger {
+        ClaimLedger {
+            account_type: CLAIM_LEDGER_ACCOUNT_TYPE,
+            bump: 255,
+            vesting_state: Pubkey::new_from_array([1; 32]),
+            next_index: 0,
+            entry_count: 0,
+            entries: [(0, 0); CLAIM_LEDGER_CAPACITY],
+        }
+    }
+
+    #[test]
+    fn claim_ledger_appends_entries() {
+        let mut ledger = empty_ledger();
+        ledger.record(100, 10);
+        ledger.record(200, 20);
+        ledger.record(300, 30);
+        assert_eq!(ledger.entry_count, 3);
+        assert_eq!(ledger.next_index, 3);
+        assert_eq!(ledger.entries[..3], [(100, 10), (200, 20), (300, 30)]);
+    }
+
+    #[test]
+    fn claim_ledger_evicts_the_oldest_entry_once_full() {
+        let mut ledger = empty_ledger();
+        for i in 0..=CLAIM_LEDGER_CAPACITY as i64 {
+            ledger.record(i, i as u64);
+        }
+        assert_eq!(ledger.entry_count, CLAIM_LEDGER_CAPACITY as u16);
+        assert_eq!(ledger.next_index, 1);
+        assert_eq!(
+            ledger.entries[0],
+            (CLAIM_LEDGER_CAPACITY as i64, CLAIM_LEDGER_CAPACITY as u64)
+        );
+        assert_eq!(ledger.entries[1], (1, 1));
+    }
+
+    #[test]
+    fn claim_ledger_round_trips_and_needs_its_account_type() {
+        let mut ledger = empty_ledger();
+        ledger.record(100, 10);
+        let mut data = [0u8; ClaimLedger::LEN];
+        ledger.pack_into_slice(&mut data);
+        assert_eq!(data[0], CLAIM_LEDGER_ACCOUNT_TYPE);
+        let unpacked = ClaimLedger::unpack(&data).unwrap();
+        assert_eq!(unpacked.vesting_state, ledger.vesting_state);
+        assert_eq!(unpacked.entries[0], (100, 10));
+
+        // A zeroed program-owned account is not a ledger
+        assert_eq!(
+            ClaimLedger::unpack(&[0u8; ClaimLedger::LEN]).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+    }
+
+    #[test]
+    fn claim_split_takes_the_fee_before_withholding() {
+        let mut state = linear_state(1_000);
+        state.claim_fee_bps = 100;
+        state.withhold_bps = 2_000;
+        assert_eq!(claim_split(&state, 1_000), Ok((10, 198, 792)));
+        assert_eq!(claim_split(&state, 0), Ok((0, 0, 0)));
+        assert_eq!(net_claimable_amount(&state, 2_000), Ok(792));
+
+        state.claim_fee_bps = 0;
+        state.withhold_bps = 0;
+        assert_eq!(claim_split(&state, 1_000), Ok((0, 0, 1_000)));
+    }
+
+    #[test]
+    fn early_claim_penalty_only_applies_to_unvested_tokens() {
+        let mut state = linear_state(1_000);
+        state.allow_early_claim = true;
+        state.early_claim_penalty_bps = 1_000;
+        assert_eq!(early_claim_penalty(&state, 1_000), Ok(100));
+        assert_eq!(early_claim_penalty(&state, 1_400), Ok(60));
+        assert_eq!(early_claim_penalty(&state, 2_000), Ok(0));
+
+        // Tokens claimed ahead of the schedule, e.g. a TGE portion, are never penalized
+        state.claimed_amount = 700;
+        assert_eq!(early_claim_penalty(&state, 1_400), Ok(30));
+
+        state.early_claim_penalty_bps = 0;
+        assert_eq!(early_claim_penalty(&state, 1_400), Ok(0));
+    }
+
+    /// A curve schedule of 1000 tokens: flat until 1200, a jump to 400 in the next second, then
+    /// linear segments to 600 at 1401 and to the full amount at 2001.
+    fn curve_state() -> VestingState {
+        let mut state = linear_state(1_000);
+        let points = [
+            (1_000, 0),
+            (1_200, 0),
+            (1_201, 400),
+            (1_401, 600),
+            (2_001, 1_000),
+        ];
+        state.vesting_end = 2_001;
+        state.curve_point_count = points.len() as u8;
+        state.curve_points[..points.len()].copy_from_slice(&points);
+        state
+    }
+
+    #[test]
+    fn curve_vests_nothing_before_the_first_point_and_everything_after_the_last() {
+        let state = curve_state();
+        assert_eq!(vested_amount(&state, 0), 0);
+        assert_eq!(vested_amount(&state, 999), 0);
+        assert_eq!(vested_amount(&state, 2_001), 1_000);
+        assert_eq!(vested_amount(&state, 5_000), 1_000);
+    }
+
+    #[test]
+    fn curve_vests_exactly_the_cumulative_amount_at_each_point() {
+        let state = curve_state();
+        for (timestamp, cumulative) in curve_points(&state) {
+            assert_eq!(vested_amount(&state, *timestamp), *cumulative);
+        }
+    }
+
+    #[test]
+    fn curve_interpolates_within_each_segment() {
+        let state = curve_state();
+        // Flat segment
+        assert_eq!(vested_amount(&state, 1_100), 0);
+        assert_eq!(vested_amount(&state, 1_199), 0);
+        // Jump of 400 within a single second
+        assert_eq!(vested_amount(&state, 1_200), 0);
+        assert_eq!(vested_amount(&state, 1_201), 400);
+        // Linear segments
+        assert_eq!(vested_amount(&state, 1_301), 500);
+        assert_eq!(vested_amount(&state, 1_701), 800);
+        assert_eq!(vested_amount(&state, 2_000), 999);
+    }
+
+    #[test]
+    fn curve_follows_the_paused_clock() {
+        let mut state = curve_state();
+        state.paused_at = 1_301;
+        assert_eq!(vested_amount(&state, 1_701), 500);
+        state.paused_at = 0;
+        state.paused_duration = 400;
+        assert_eq!(vested_amount(&state, 1_701), 500);
+        assert_eq!(vested_amount(&state, 2_401), 1_000);
+    }
 }
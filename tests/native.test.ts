@@ -42,4 +42,792 @@ describe("Vesting Program", () => {
   it("should not claim vested funds", async () => {
     // test logic for trying to claimvested funds before vested periode is over
   });
+
+  it("should claim the TGE portion right after init", async () => {
+    // test logic for claiming tge_bps of the vested amount right after initializing vesting
+  });
+
+  it("should claim the streamed remainder later", async () => {
+    // test logic for claiming the linearly streamed remainder after the TGE portion was claimed
+  });
+
+  it("should not initialize vesting with tge_bps above 10000", async () => {
+    // test logic for initializing vesting with a TGE share above 100%
+  });
+
+  it("should return vault dust to the funder after the final claim", async () => {
+    // test logic for leaving dust in the vault, fully claiming, and asserting the dust returns to the funder's token account
+  });
+
+  it("should split a vesting schedule and claim from both halves", async () => {
+    // test logic for splitting unvested tokens into a new schedule and claiming from the source and the new schedule
+  });
+
+  it("should not split more than the remaining unvested amount", async () => {
+    // test logic for splitting an amount above the unvested tokens
+  });
+
+  it("should merge two compatible schedules", async () => {
+    // test logic for merging two schedules with identical parameters and asserting summed amount and claimed amount
+  });
+
+  it("should not merge schedules with mismatched parameters", async () => {
+    // test logic for merging two schedules with a different vesting end
+  });
+
+  it("should claim the same amount through the fast path at and after vesting_end", async () => {
+    // test logic for claiming at vesting_end and after vesting_end and comparing against the linear path result
+  });
+
+  it("should not recover before the recovery delay has elapsed", async () => {
+    // test logic for requesting recovery and recovering before RECOVERY_DELAY
+  });
+
+  it("should recover to a new recipient after the recovery delay", async () => {
+    // test logic for requesting recovery, waiting RECOVERY_DELAY, recovering, and claiming as the new recipient
+  });
+
+  it("should not claim to a recipient token account owned by someone else", async () => {
+    // test logic for claiming into a token account whose owner is not the vesting receiver
+  });
+
+  it("should claim into a recipient token account owned by a PDA receiver", async () => {
+    // test logic for initializing vesting with a PDA of another program as the recipient, claiming into a token account owned by that PDA and asserting it receives the claimable amount
+  });
+
+  it("should drip tokens at a steady rate per second", async () => {
+    // test logic for claiming a drip schedule twice and asserting rate_per_second times the elapsed seconds each time
+  });
+
+  it("should stop drip claims once the vault is drained", async () => {
+    // test logic for claiming a drip schedule past its funded amount and asserting further claims fail
+  });
+
+  it("should leave the vesting state all-zero when schedule validation fails", async () => {
+    // test logic for initializing vesting with a vesting_end in the past and asserting the state account data is still all zero
+  });
+
+  it("should not move any funder tokens when init validation fails", async () => {
+    // test logic for initializing vesting with a co-funder and a vault of another mint, asserting MintMismatch and that the funder's and co-funder's token balances are unchanged
+  });
+
+  it("should compute the claim outcome for a partially vested schedule", async () => {
+    // test logic for compute_claim_outcome mid-stream asserting transferred, total_claimed and is_final == false
+  });
+
+  it("should compute the claim outcome for a fully vested schedule", async () => {
+    // test logic for compute_claim_outcome after vesting_end asserting is_final == true
+  });
+
+  it("should return the claim outcome as return data", async () => {
+    // test logic for claiming and decoding transferred, total_claimed and is_final from the transaction return data
+  });
+
+  it("should init and claim a self-vesting schedule", async () => {
+    // test logic for initializing vesting with the funder as recipient and claiming into the same token account the funder funded from
+  });
+
+  it("should split and merge a self-vesting schedule", async () => {
+    // test logic for splitting and merging a schedule whose funder and recipient are the same key
+  });
+
+  it("should claim using the decimals snapshotted at init", async () => {
+    // test logic for claiming with transfer_checked against the mint decimals recorded in the vesting state
+  });
+
+  it("should not claim with a mint of mismatched decimals", async () => {
+    // test logic for claiming while passing a mint whose decimals differ from the snapshot
+  });
+
+  it("should keep claimed_amount accumulation safe for near-u64::MAX amounts", async () => {
+    // test logic for vesting an amount near u64::MAX, claiming twice, and asserting claimed_amount never overflows
+  });
+
+  it("should report 0 seconds until next vest while a linear schedule streams", async () => {
+    // test logic for seconds_until_next_vest mid-stream on a linear schedule
+  });
+
+  it("should report 0 seconds until next vest while a drip schedule streams", async () => {
+    // test logic for seconds_until_next_vest on a drip schedule with tokens left
+  });
+
+  it("should report no next vest once fully vested", async () => {
+    // test logic for seconds_until_next_vest after vesting_end returning None
+  });
+
+  it("should estimate a single claim for an uncapped schedule", async () => {
+    // test logic for estimate_claim_count on a linear, a drip and a stepped schedule with tokens left asserting 1, and on a fully claimed schedule asserting 0
+  });
+
+  it("should claim with transfers signed by the vault authority PDA", async () => {
+    // test logic for deriving ["vault-auth", vesting_state] as the vault owner and claiming through it
+  });
+
+  it("should derive a stable vault address whose bump regenerates it", async () => {
+    // test logic for deriving vault_address twice for the same vesting state, mint and token program, asserting the results are equal, that createProgramAddress with the returned bump yields the same address and that it equals getAssociatedTokenAddressSync for the vault authority
+  });
+
+  it("should not initialize vesting with a vault not owned by the vault authority PDA", async () => {
+    // test logic for initializing vesting with a vault owned by the funder
+  });
+
+  it("should not initialize vesting with a vault below the rent-exempt minimum", async () => {
+    // test logic for initializing vesting with a vault token account funded below the rent-exempt minimum for its size and asserting AccountNotRentExempt
+  });
+
+  it("should read the claimable amounts of three schedules at once", async () => {
+    // test logic for passing three vesting states as remaining accounts and decoding three u64 claimable amounts from return data
+  });
+
+  it("should not read more than MAX_BULK_CLAIMABLE schedules", async () => {
+    // test logic for passing 129 vesting states and asserting rejection
+  });
+
+  it("should not claim the full amount at vesting_end - 1", async () => {
+    // test logic for claiming one second before vesting_end and asserting less than the full amount is transferred
+  });
+
+  it("should claim the full amount at exactly vesting_end", async () => {
+    // test logic for claiming at vesting_end and asserting the remaining amount is transferred
+  });
+
+  it("should claim the full amount at vesting_end + 1", async () => {
+    // test logic for claiming one second after vesting_end and asserting the remaining amount is transferred
+  });
+
+  it("should split a claim between the treasury and the recipient", async () => {
+    // test logic for claiming with claim_fee_bps set and asserting the treasury receives transferred * claim_fee_bps / 10000 and the recipient the rest
+  });
+
+  it("should not claim with a treasury other than the one recorded at init", async () => {
+    // test logic for claiming with a different treasury token account
+  });
+
+  it("should not claim into a recipient token account of another mint", async () => {
+    // test logic for claiming into a recipient token account of a different mint and asserting MintMismatch
+  });
+
+  it("should invoke the registered claim hook after a claim", async () => {
+    // test logic for registering a mock hook program at init, claiming, and asserting the hook recorded the encoded claim outcome
+  });
+
+  it("should not claim when the claim hook program passed differs from the registered one", async () => {
+    // test logic for claiming with a different program in place of the claim hook
+  });
+
+  it("should stream the reduced amount after a partial revoke", async () => {
+    // test logic for revoking part of the unvested tokens mid-schedule, asserting the funder receives them and the vested amount is unchanged, then claiming at vesting_end and asserting the recipient receives the reduced amount
+  });
+
+  it("should not revoke more than the unvested amount", async () => {
+    // test logic for revoking more than amount minus vested and asserting InvalidArgument
+  });
+
+  it("should not revoke without the funder's signature", async () => {
+    // test logic for revoking with a signer other than the recorded funder
+  });
+
+  it("should report nothing to claim after revoking the rest of a claimed schedule", async () => {
+    // test logic for claiming everything vested mid-stream, revoking the whole unvested remainder, claiming and asserting NothingToClaim, then calling close_schedule and asserting the vesting state is closed
+  });
+
+  it("should pack a known vesting state into the golden bytes", async () => {
+    // test logic for initializing a schedule with fixed keys and parameters, reading the 303-byte vesting state account and asserting it equals the golden byte array field by field at the documented offsets
+  });
+
+  it("should unpack the golden bytes into the known vesting state", async () => {
+    // test logic for decoding the golden byte array with the IDL layout and asserting every field, including little-endian integers and the is_initialized byte at offset 88
+  });
+
+  it("should report zero claimable before vesting_start", async () => {
+    // test logic for reading the claimable amount with the clock set before vesting_start and asserting 0, then claiming and asserting NothingToClaim
+  });
+
+  it("should initialize the program config with the upgrade authority", async () => {
+    // test logic for calling init_config signed by the upgrade authority and asserting the config PDA holds the claim fee, treasury and allowed token programs
+  });
+
+  it("should not initialize or update the program config without the upgrade authority", async () => {
+    // test logic for calling init_config and update_config signed by another keypair and asserting MissingRequiredSignature
+  });
+
+  it("should snapshot the claim fee and treasury from the program config at init", async () => {
+    // test logic for updating the config, initializing a schedule and asserting its claim_fee_bps and treasury match the config
+  });
+
+  it("should not initialize vesting with a token program outside the allowed list", async () => {
+    // test logic for initializing vesting with a token program not listed in the config and asserting IncorrectProgramId
+  });
+
+  it("should not claim into a frozen recipient token account", async () => {
+    // test logic for freezing the recipient token account with the mint freeze authority, claiming and asserting RecipientFrozen
+  });
+
+  it("should not initialize vesting shorter than the configured minimum duration", async () => {
+    // test logic for setting min_duration to 1 hour in the config, initializing a schedule ending in 30 minutes and asserting InvalidArgument
+  });
+
+  it("should initialize vesting lasting the configured minimum duration", async () => {
+    // test logic for setting min_duration to 1 hour in the config and initializing a schedule ending in exactly 1 hour
+  });
+
+  it("should close the vault and return its rent to the funder after the final claim", async () => {
+    // test logic for claiming at vesting_end, asserting the vault token account no longer exists and the funder's lamports grew by the vault rent
+  });
+
+  it("should claim milestones of a stepped schedule out of order", async () => {
+    // test logic for initializing a schedule with step_count 4, warping past the third milestone, claiming milestone 2 then milestone 0 and asserting each transfers exactly its tranche
+  });
+
+  it("should not claim a milestone that has not been reached", async () => {
+    // test logic for claiming the last milestone before vesting_end and asserting MilestoneNotReached
+  });
+
+  it("should not claim a milestone twice", async () => {
+    // test logic for claiming milestone 0 twice, and claiming it after a regular claim, asserting MilestoneAlreadyClaimed
+  });
+
+  it("should record claimed milestone combinations in the bitmap", async () => {
+    // test logic for a schedule with step_count 8, claiming milestones 1, 3 and 4 individually and asserting claimed_milestones == 0b11010, then a regular claim past milestone 6 and asserting claimed_milestones == 0b1111111
+  });
+
+  it("should record the last milestone of a 64-step schedule in the top bit", async () => {
+    // test logic for a schedule with step_count 64, claiming milestone 63 at vesting_end and asserting claimed_milestones == 1 << 63
+  });
+
+  it("should keep the vault open when residual tokens exceed the dust threshold until swept", async () => {
+    // test logic for over-funding the vault, claiming at vesting_end, asserting the vault is still open, then calling close_vault as the funder and asserting the residual is returned and the vault closed
+  });
+
+  it("should not close the vault of a schedule that is not fully claimed", async () => {
+    // test logic for calling close_vault before the final claim and asserting InvalidAccountData
+  });
+
+  it("should simulate the claimable amount from packed account data", async () => {
+    // test logic for fetching the vesting state account data, evaluating simulate_claimable at several timestamps and asserting it matches the claimable amount read on-chain at the same clock
+  });
+
+  it("should simulate zero claimable for a fully claimed schedule", async () => {
+    // test logic for fetching the account data after the final claim and asserting simulate_claimable returns 0
+  });
+
+  it("should shift the effective end by a paused interval", async () => {
+    // test logic for pausing a schedule mid-way for an hour, resuming, and asserting nothing vested while paused and the full amount only vests an hour after vesting_end
+  });
+
+  it("should claim already vested tokens while paused", async () => {
+    // test logic for pausing a schedule and claiming the tokens vested before the pause
+  });
+
+  it("should read the effective end shifted by one paused interval", async () => {
+    // test logic for pausing for an hour, resuming and asserting read_effective_end returns vesting_end + 1 hour and the seconds left until then
+  });
+
+  it("should read the effective end shifted by two paused intervals", async () => {
+    // test logic for pausing for an hour twice with a resume in between, asserting read_effective_end returns vesting_end + 2 hours, and while paused again that it moves out with the clock
+  });
+
+  it("should not shift the effective end by a frozen interval", async () => {
+    // test logic for freezing a schedule for an hour, asserting claims fail with ScheduleFrozen, thawing and asserting the amount vested matches an unfrozen schedule
+  });
+
+  it("should not pause or freeze without the funder's signature", async () => {
+    // test logic for calling set_paused and set_frozen signed by the recipient and asserting MissingRequiredSignature
+  });
+
+  it("should not claim with the vault passed as the recipient token account", async () => {
+    // test logic for claiming with the vault in place of the recipient token account and asserting DuplicateAccount
+  });
+
+  it("should not claim with the vesting state passed as the vault", async () => {
+    // test logic for claiming with the vesting state account in place of the vault and asserting DuplicateAccount
+  });
+
+  it("should not split into the source vault", async () => {
+    // test logic for splitting with the source vault passed as the new vault and asserting DuplicateAccount
+  });
+
+  it("should not merge a schedule into itself", async () => {
+    // test logic for merging with the same vesting state passed as target and source and asserting DuplicateAccount
+  });
+
+  it("should store the schedule hash at init", async () => {
+    // test logic for initializing a schedule, recomputing the SHA-256 hash of its canonical parameters client-side and asserting it matches schedule_hash
+  });
+
+  it("should not claim from a vault with an active delegate", async () => {
+    // test logic for approving a delegate on the vault, claiming and asserting UnexpectedDelegate
+  });
+
+  it("should not initialize vesting with a delegated vault", async () => {
+    // test logic for initializing vesting with a vault that has a delegate and asserting UnexpectedDelegate
+  });
+
+  it("should read a 4-sample linear vesting curve", async () => {
+    // test logic for reading the curve of a linear schedule from vesting_start to vesting_end with 4 samples and asserting the decoded timestamps are evenly spaced and the vested amounts are 0, amount / 3, 2 * amount / 3 and amount
+  });
+
+  it("should not read more than MAX_CURVE_SAMPLES curve samples", async () => {
+    // test logic for reading the curve with 65 samples and asserting InvalidArgument
+  });
+
+  it("should only release whole units in intermediate claims", async () => {
+    // test logic for initializing a schedule with unit_size set, claiming mid-stream several times and asserting every claimed_amount is a multiple of unit_size
+  });
+
+  it("should drain the remainder on the final claim of a unit-aligned schedule", async () => {
+    // test logic for initializing a schedule whose amount is not a multiple of unit_size, claiming at vesting_end and asserting the full remainder is transferred
+  });
+
+  it("should log the remaining compute units around a claim with the profiling feature", async () => {
+    // test logic for claiming against a program built with --features profiling and asserting the transaction logs contain the start and end compute unit lines for instruction 1
+  });
+
+  it("should vest 5000 bps of the funder's token balance", async () => {
+    // test logic for initializing vesting with amount_in_bps set and amount 5000 against a funder token account holding a known balance, asserting the vesting state amount and the vault balance are half of it
+  });
+
+  it("should fund a schedule from two co-funders in one init", async () => {
+    // test logic for initializing vesting with one co-funder amount and its token account and owner as remaining accounts, asserting the vault holds amount, the co-funder's balance dropped by its share, the funder's by the rest, and the recorded funder is the primary funder
+  });
+
+  it("should not initialize vesting with co-funder amounts above the amount", async () => {
+    // test logic for initializing vesting with co-funder amounts summing to more than amount and asserting InvalidArgument
+  });
+
+  it("should not claim a dual-control schedule with a single signature", async () => {
+    // test logic for initializing vesting with dual_control set, claiming signed by only the receiver, then by only the funder, and asserting MissingRequiredSignature both times
+  });
+
+  it("should claim a dual-control schedule signed by the receiver and the funder", async () => {
+    // test logic for initializing vesting with dual_control set, claiming signed by both the receiver and the funder and asserting the recipient receives the claimable amount
+  });
+
+  it("should top up a vesting state account below the rent-exempt minimum", async () => {
+    // test logic for creating a vesting state account funded below the rent-exempt minimum, claiming and asserting the rent warning is logged, then calling top_up_rent and asserting the account holds exactly the rent-exempt minimum
+  });
+
+  it("should report a schedule with vested tokens as claimable", async () => {
+    // test logic for reading is_claimable mid-stream and asserting the return data is [1]
+  });
+
+  it("should report a schedule without vested tokens as not claimable", async () => {
+    // test logic for reading is_claimable right after claiming everything vested so far, and after the final claim, asserting the return data is [0]
+  });
+
+  it("should compute vesting_end from a duration", async () => {
+    // test logic for initializing vesting with end_is_duration set and a 4-year duration, asserting vesting_end equals vesting_start plus the duration
+  });
+
+  it("should not initialize vesting with a non-positive duration", async () => {
+    // test logic for initializing vesting with end_is_duration set and durations of 0 and -1, asserting InvalidArgument
+  });
+
+  it("should not revoke an irrevocable schedule", async () => {
+    // test logic for initializing vesting with irrevocable set, calling revoke_partial as the funder and asserting Irrevocable
+  });
+
+  it("should not recover an irrevocable schedule", async () => {
+    // test logic for initializing vesting with irrevocable set and a recovery authority, calling request_recovery and asserting Irrevocable
+  });
+
+  it("should hand over the funder role once the new funder accepts", async () => {
+    // test logic for calling set_funder as the funder, asserting revoke still requires the old funder, calling accept_funder as the new funder and asserting pause and revoke now require the new funder
+  });
+
+  it("should not accept the funder role without being proposed", async () => {
+    // test logic for calling accept_funder signed by a key other than the pending funder and asserting MissingRequiredSignature
+  });
+
+  it("should keep sending revoked tokens to the outgoing funder after a handover", async () => {
+    // test logic for handing over the funder role without a refund owner, revoking as the new funder and asserting the tokens go to a token account of the outgoing funder
+  });
+
+  it("should send revoked tokens to the refund owner passed to set_funder", async () => {
+    // test logic for handing over the funder role with the new funder as refund owner, revoking and asserting the tokens go to the new funder's token account
+  });
+
+  it("should stream at the slower rate after extending mid-stream without reducing past claims", async () => {
+    // test logic for claiming half way, extending vesting_end, asserting claimed_amount is unchanged, nothing is claimable until the recomputed curve from vesting_start to the new end passes it, and the full amount vests at the new end
+  });
+
+  it("should not extend a schedule to an earlier end", async () => {
+    // test logic for calling extend_vesting with the current vesting_end and an earlier one and asserting InvalidArgument
+  });
+
+  it("should close a fully claimed schedule and return its rent to the funder", async () => {
+    // test logic for claiming at vesting_end, calling close_schedule and asserting the vesting state account no longer exists and the funder's lamports grew by its rent
+  });
+
+  it("should close a fully revoked schedule along with its empty vault", async () => {
+    // test logic for revoking the whole unvested remainder after claiming the vested tokens, calling close_schedule and asserting both the vesting state and the vault are closed
+  });
+
+  it("should not close a schedule that is not fully claimed", async () => {
+    // test logic for calling close_schedule mid-stream and asserting NotFullyClaimed
+  });
+
+  it("should not close a schedule whose vault still holds tokens", async () => {
+    // test logic for over-funding the vault, claiming at vesting_end, calling close_schedule and asserting VaultNotEmpty
+  });
+
+  it("should not close a schedule with a vault other than its own", async () => {
+    // test logic for fully claiming a schedule whose vault still holds dust, calling close_schedule with an unallocated account in place of the vault and asserting InvalidAccountData
+  });
+
+  it("should fund a schedule from an SPL multisig authority", async () => {
+    // test logic for initializing vesting with a 2-of-3 SPL token multisig as the funder, its token account after the config, two of its signers as remaining accounts with multisig_signer_count 2 and asserting the vault receives the amount
+  });
+
+  it("should not fund a schedule from a multisig without enough signers", async () => {
+    // test logic for initializing vesting with a 2-of-3 SPL token multisig and a single signer and asserting MissingRequiredSignature
+  });
+
+  it("should not claim before the schedule is activated", async () => {
+    // test logic for initializing vesting, warping past vesting_start, claiming and asserting NotActive, then activating as the funder and claiming the vested amount
+  });
+
+  it("should not activate a schedule without the funder's signature", async () => {
+    // test logic for calling activate signed by the recipient and asserting MissingRequiredSignature
+  });
+
+  it("should stream a mid-stream top-up over the remaining window without over-release", async () => {
+    // test logic for claiming at the midpoint of a linear schedule, topping up by amount, asserting nothing more is claimable right after, that a quarter later claimable equals (amount / 2 + amount) / 2 and that the full doubled amount vests exactly at vesting_end
+  });
+
+  it("should not top up a schedule past its end", async () => {
+    // test logic for topping up a linear schedule after vesting_end and asserting InvalidArgument
+  });
+
+  it("should surface every VestingError as the matching custom program error code", async () => {
+    // test logic for triggering NothingToClaim, MathOverflow, DuplicateAccount and NotActive and asserting each transaction fails with ProgramError::Custom carrying the error's IDL code
+  });
+
+  it("should vest an NFT all at once at vesting_end", async () => {
+    // test logic for initializing vesting in NFT mode with a 0-decimals mint of supply 1, asserting claims fail with NothingToClaim right before vesting_end and the NFT is transferred by a claim at vesting_end
+  });
+
+  it("should not vest a mint with decimals or supply above 1 in NFT mode", async () => {
+    // test logic for initializing vesting in NFT mode with a 6-decimals mint and with a 0-decimals mint of supply 2, asserting InvalidArgument
+  });
+
+  it("should not claim after the claim window expired", async () => {
+    // test logic for initializing vesting with claim_expiry one day after vesting_end, warping past claim_expiry, claiming and asserting ClaimExpired
+  });
+
+  it("should let the funder reclaim unclaimed tokens after the claim window expired", async () => {
+    // test logic for claiming half way, warping past claim_expiry, calling reclaim_expired as the funder and asserting the funder receives amount minus claimed_amount and the schedule is closed out
+  });
+
+  it("should not reclaim before the claim window expired", async () => {
+    // test logic for calling reclaim_expired before claim_expiry and asserting InvalidArgument
+  });
+
+  it("should round-trip a minimal schedule created with init_simple", async () => {
+    // test logic for calling init_simple with a recipient, amount and vesting_end, decoding the vesting state and asserting every optional field holds its default, then activating and claiming at vesting_end and asserting the full amount is transferred
+  });
+
+  it("should create schedules up to the per-funder cap", async () => {
+    // test logic for setting max_schedules_per_funder to 2 in the config, initializing the funder quota, creating two schedules and asserting the quota counts 2 active schedules
+  });
+
+  it("should fail to create a schedule beyond the per-funder cap", async () => {
+    // test logic for setting max_schedules_per_funder to 2, creating two schedules and asserting a third init fails with FunderQuotaExceeded
+  });
+
+  it("should free a quota slot when a capped schedule is closed", async () => {
+    // test logic for filling the per-funder cap, fully claiming and closing one schedule with close_schedule and asserting a new schedule can be created
+  });
+
+  it("should reject a vesting state account one byte short of the layout", async () => {
+    // test logic for claiming against a program-owned vesting state account of VestingState.LEN - 1 bytes and asserting InvalidAccountData instead of a program panic
+  });
+
+  it("should return everything unclaimed to the funder when the recipient relinquishes mid-stream", async () => {
+    // test logic for claiming part of a linear schedule, warping half way, calling relinquish as the recipient and asserting the funder receives amount minus claimed_amount and the schedule is closed out
+  });
+
+  it("should fail to relinquish a schedule without the recipient's signature", async () => {
+    // test logic for calling relinquish signed by the funder instead of the recipient and asserting MissingRequiredSignature
+  });
+
+  it("should append each claim to the claim ledger", async () => {
+    // test logic for creating the claim ledger PDA with init_claim_ledger, claiming three times with it as the last account and asserting entry_count is 3 with the (timestamp, amount) of each claim in order
+  });
+
+  it("should evict the oldest claim once the claim ledger is full", async () => {
+    // test logic for claiming CLAIM_LEDGER_CAPACITY + 1 times with a ledger and asserting entry_count stays at CLAIM_LEDGER_CAPACITY and the first claim was overwritten by the last one
+  });
+
+  it("should reject a claim ledger bound to another schedule", async () => {
+    // test logic for creating the claim ledger PDA of schedule A, claiming schedule B with it and asserting InvalidSeeds, then claiming with a zeroed program-owned account of ClaimLedger.LEN and asserting UninitializedAccount
+  });
+
+  it("should reject a payload one byte too long or too short for every instruction", async () => {
+    // test logic for sending each instruction tag with its expected payload length plus one and minus one byte (where non-negative) and asserting InvalidInstructionData before any account is read
+  });
+
+  it("should reject trailing payload bytes on instructions without parameters", async () => {
+    // test logic for calling claim_vesting, close_schedule and activate with one extra payload byte and asserting InvalidInstructionData
+  });
+
+  it("should reject an init payload whose co-funder amounts are not whole u64s", async () => {
+    // test logic for sending init with 138 + 4 payload bytes and asserting InvalidInstructionData
+  });
+
+  it("should unlock a fixed amount at every period boundary of a periodic schedule", async () => {
+    // test logic for initializing a periodic schedule of 1000 tokens with period_seconds of one day and per_period of 300, warping to just before and just after the 1st, 2nd and 3rd day boundaries and asserting the claimable amount steps 0, 300, 600 and 900
+  });
+
+  it("should release the remainder of a periodic schedule at its last period", async () => {
+    // test logic for initializing a periodic schedule of 1000 tokens with per_period of 300, asserting vesting_end is 4 periods after the start and that claiming at vesting_end releases the last 100 tokens
+  });
+
+  it("should fail to initialize a periodic schedule with a TGE portion", async () => {
+    // test logic for initializing a schedule with period_seconds and tge_bps both set and asserting InvalidArgument
+  });
+
+  it("should claim a KYC-gated schedule with a valid attestation", async () => {
+    // test logic for setting a KYC verifier in the config, initializing a schedule with kyc_required, creating an attestation account owned by the verifier for the receiver expiring in a day and asserting the claim succeeds
+  });
+
+  it("should fail to claim a KYC-gated schedule without an attestation", async () => {
+    // test logic for claiming a schedule with kyc_required without passing an attestation account and asserting NotEnoughAccountKeys
+  });
+
+  it("should fail to claim a KYC-gated schedule with an expired attestation", async () => {
+    // test logic for claiming with an attestation whose expires_at is in the past and asserting KycAttestationExpired
+  });
+
+  it("should fail to claim a KYC-gated schedule with an attestation of another verifier or recipient", async () => {
+    // test logic for claiming with an attestation owned by another program, then one attesting another address, and asserting InvalidKycAttestation for both
+  });
+
+  it("should close finished schedules in bulk and skip unfinished ones", async () => {
+    // test logic for fully claiming two schedules, leaving a third mid-stream, calling close_many with all three (state, vault, vault authority) groups and asserting the first two states and vaults are closed with their rent sent to the funder while the third is untouched
+  });
+
+  it("should fail to close many schedules with an incomplete account group", async () => {
+    // test logic for calling close_many with a trailing group missing its vault authority and asserting NotEnoughAccountKeys
+  });
+
+  it("should fail to claim a schedule starting next week with NothingVestedYet", async () => {
+    // test logic for initializing a schedule with vesting_start one week from now and a 10% TGE, claiming right away and asserting NothingVestedYet, and asserting starts_at reports the start a week out
+  });
+
+  it("should vest a future-start schedule from its start", async () => {
+    // test logic for initializing a schedule starting next week and ending a week later, warping to the start and asserting the TGE portion is claimable, then warping half way and asserting half of the remainder is claimable
+  });
+
+  it("should fail to initialize a schedule starting in the past", async () => {
+    // test logic for initializing a schedule with vesting_start one second before the current timestamp and asserting InvalidArgument
+  });
+
+  it("should attach a memo to the claim transfer", async () => {
+    // test logic for claiming with a memo payload and the SPL Memo program account, fetching the transaction and asserting the Memo program logged the memo right before the transfer to the recipient
+  });
+
+  it("should fail to claim with a memo longer than MAX_CLAIM_MEMO_LEN", async () => {
+    // test logic for claiming with a memo of MAX_CLAIM_MEMO_LEN + 1 bytes and asserting InvalidInstructionData
+  });
+
+  it("should fail to claim with a memo that is not UTF-8", async () => {
+    // test logic for claiming with the memo bytes [0xff, 0xfe] and asserting InvalidInstructionData
+  });
+
+  it("should shrink an oversized vesting state and refund the freed rent to the funder", async () => {
+    // test logic for initializing a schedule in an account of VestingState.LEN + 64 bytes, calling shrink_state and asserting the account is VestingState.LEN bytes, still rent-exempt with its state intact, and the funder received the rent difference
+  });
+
+  it("should leave a vesting state of the current layout untouched", async () => {
+    // test logic for calling shrink_state on an account of exactly VestingState.LEN bytes and asserting its size and lamports are unchanged
+  });
+
+  it("should succeed when init_idempotent is retried with identical parameters", async () => {
+    // test logic for calling init_idempotent, then sending the same init_idempotent again and asserting it succeeds without moving tokens and the vesting state is unchanged
+  });
+
+  it("should fail when init_idempotent is retried with conflicting parameters", async () => {
+    // test logic for calling init_idempotent, then calling it again on the same vesting state with a different amount and asserting AccountAlreadyInitialized
+  });
+
+  it("should allow recovery of a lock_after_first_claim schedule before the first claim", async () => {
+    // test logic for initializing a schedule with lock_after_first_claim and a recovery authority, requesting a recovery before any claim, warping past RECOVERY_DELAY, recovering and asserting the receiver was redirected
+  });
+
+  it("should fail to recover a lock_after_first_claim schedule after the first claim", async () => {
+    // test logic for initializing a schedule with lock_after_first_claim and a recovery authority, claiming once, then requesting a recovery and asserting RecipientLocked
+  });
+
+  it("should fail to execute a recovery requested before the recipient got locked", async () => {
+    // test logic for requesting a recovery on a lock_after_first_claim schedule, claiming before RECOVERY_DELAY elapses, warping past it and asserting recover fails with RecipientLocked
+  });
+
+  it("should report vested_bps of 0, 5000 and 10000 for a linear schedule", async () => {
+    // test logic for computing vested_bps of a linear schedule without TGE at vesting_start, half way and vesting_end and asserting 0, 5000 and 10000
+  });
+
+  it("should report vested_bps of 0, 5000 and 10000 for a stepped schedule", async () => {
+    // test logic for computing vested_bps of a 4-step schedule without TGE before the first milestone, at the second milestone and at vesting_end and asserting 0, 5000 and 10000
+  });
+
+  it("should fail to initialize with a system-owned account as the vault", async () => {
+    // test logic for initializing a schedule with a system-owned account of spl_token Account size as the vault and asserting IncorrectProgramId
+  });
+
+  it("should fail to claim with a system-owned account as the vault", async () => {
+    // test logic for claiming with a system-owned account passed as the vault and asserting IncorrectProgramId
+  });
+
+  it("should keep a launch schedule pending before the launch timestamp is set", async () => {
+    // test logic for initializing the launch of an authority, initializing a schedule referencing it with a one week duration, asserting claims fail with NothingVestedYet and apply_launch fails with LaunchPending
+  });
+
+  it("should start a launch schedule at the launch timestamp", async () => {
+    // test logic for initializing a launch schedule, setting the launch timestamp, calling apply_launch and asserting vesting_start is the launch timestamp and vesting_end is a week later, then warping half way and asserting half is claimable
+  });
+
+  it("should fail to set the launch timestamp twice", async () => {
+    // test logic for setting the launch timestamp, setting it again and asserting InvalidArgument
+  });
+
+  it("should fail to initialize a launch schedule with an absolute end", async () => {
+    // test logic for initializing a schedule referencing a launch without end_is_duration and asserting InvalidArgument
+  });
+
+  it("should preview the gross and net claimable amounts with a claim fee", async () => {
+    // test logic for configuring a 100 bps claim fee, initializing a schedule, warping half way, calling read_claim_preview and asserting the return data holds the gross claimable amount and the gross amount minus 1%, then claiming and asserting the recipient received exactly the net amount
+  });
+
+  it("should fail to extend a paused-and-resumed schedule to an end that overflows i64", async () => {
+    // test logic for pausing and resuming a linear schedule so paused_duration > 0, extending it to i64::MAX and asserting MathOverflow with the vesting state unchanged
+  });
+
+  it("should initialize with the associated token account vault when require_ata_vault is set", async () => {
+    // test logic for updating the config with require_ata_vault, creating the associated token account of the vault authority for the mint, initializing a schedule with it as the vault and asserting success
+  });
+
+  it("should fail to initialize with an arbitrary vault when require_ata_vault is set", async () => {
+    // test logic for updating the config with require_ata_vault, initializing a schedule with a non-associated token account owned by the vault authority and asserting InvalidSeeds
+  });
+
+  it("should parse version 1 instruction data", async () => {
+    // test logic for sending a claim as [1, INSTRUCTION_VERSION] followed by no payload and asserting the vested tokens are transferred
+  });
+
+  it("should fail with an unknown instruction version", async () => {
+    // test logic for sending a claim as [1, 2] and asserting InvalidInstructionData with the "Unsupported instruction version 2" log, then sending [1] without a version byte and asserting InvalidInstructionData
+  });
+
+  it("should let a crank claim an auto_claim schedule into the receiver's ATA", async () => {
+    // test logic for initializing a schedule with auto_claim, warping half way, calling crank_claim signed by an unrelated keypair with the receiver's associated token account and asserting half the tokens arrived there
+  });
+
+  it("should fail to crank a claim into a token account other than the receiver's ATA", async () => {
+    // test logic for calling crank_claim on an auto_claim schedule with another token account owned by the receiver and asserting InvalidAccountData
+  });
+
+  it("should fail to crank a claim of a schedule without auto_claim", async () => {
+    // test logic for calling crank_claim on a schedule created without auto_claim and asserting InvalidArgument
+  });
+
+  it("should fail to claim without the receiver's signature", async () => {
+    // test logic for calling claim_vesting on an auto_claim schedule signed by an unrelated keypair in place of the receiver and asserting MissingRequiredSignature
+  });
+
+  it("should accrue nothing while paused and resume at the pre-pause rate", async () => {
+    // test logic for a linear schedule of 1000 tokens over 1000 seconds: warping to 100s and asserting 100 claimable, pausing for exactly 300 seconds and asserting still 100 claimable, resuming and warping another 100 seconds and asserting 200 claimable, i.e. one token per second before and after the 300 second gap
+  });
+
+  it("should report the time until a start delayed by a pause", async () => {
+    // test logic for a schedule starting in an hour, pausing for 30 minutes before the start, resuming, warping to the original start and asserting seconds_until_next_vest reports the remaining 30 minutes
+  });
+
+  it("should claim from a mid-stream state set with set_test_state", async () => {
+    // test logic for a program built with the test-utils feature: initializing a schedule of 1000 tokens, calling set_test_state with vesting_start 500 seconds ago, vesting_end 500 seconds from now and claimed_amount 200, then claiming and asserting 300 tokens are transferred
+  });
+
+  it("should read the unlock calendar of a 4-milestone schedule", async () => {
+    // test logic for initializing a 4-step schedule of 1000 tokens over 4000 seconds, claiming the first milestone after 1000 seconds, calling read_milestones with first 0 and asserting four 18-byte entries (i, vesting_start + 1000 * (i + 1), 250, i == 0)
+  });
+
+  it("should page through milestones past MAX_MILESTONE_ENTRIES", async () => {
+    // test logic for initializing a 64-step schedule, calling read_milestones with first 0 and asserting MAX_MILESTONE_ENTRIES entries, then with first MAX_MILESTONE_ENTRIES and asserting the remaining 8
+  });
+
+  it("should compute the claim fee of a near-u64::MAX claim without overflow", async () => {
+    // test logic for configuring a 100 bps claim fee, initializing a schedule of u64::MAX - 1 tokens of a mint with that supply, warping past vesting_end, claiming and asserting the treasury received exactly (u64::MAX - 1) * 100 / 10000 and the recipient the rest
+  });
+
+  it("should return the schedule status after init", async () => {
+    // test logic for initializing a schedule of 1000 tokens and decoding the 18-byte return data as instruction 0, claimed 0, remaining 1000 and flags OPEN
+  });
+
+  it("should append the schedule status to the claim outcome", async () => {
+    // test logic for warping halfway through a 1000 token schedule, claiming, decoding the first 17 bytes as the ClaimOutcome and the last 18 bytes as instruction 1, claimed 500, remaining 500 and flags OPEN
+  });
+
+  it("should return the schedule status after a partial revoke", async () => {
+    // test logic for revoking 400 of an unclaimed 1000 token schedule and decoding the return data as instruction 7, claimed 0, remaining 600 and flags OPEN
+  });
+
+  it("should rescue stray tokens sent to the vault authority", async () => {
+    // test logic for initializing a schedule, minting 50 tokens of a second mint into a token account owned by the schedule's vault authority PDA, calling rescue_tokens signed by the upgrade authority and asserting the destination received 50 tokens while the vault balance is unchanged
+  });
+
+  it("should refuse to rescue a token account of the schedule mint", async () => {
+    // test logic for calling rescue_tokens on the schedule's vault and asserting InvalidAccountData, then calling it on a stray account of another mint signed by the funder and asserting MissingRequiredSignature
+  });
+
+  it("should vest a piecewise-linear curve across its segments", async () => {
+    // test logic for initializing a curve schedule of 1000 tokens with points (t0, 0), (t0 + 100, 0), (t0 + 200, 400), (t0 + 400, 1000), asserting 0 claimable at t0 + 50 (cliff), 200 at t0 + 150, 400 at t0 + 200, 700 at t0 + 300 and 1000 at t0 + 400
+  });
+
+  it("should report the time until a curve rises after a flat segment", async () => {
+    // test logic for initializing a curve schedule with a flat first segment from t0 to t0 + 100 and asserting seconds_until_next_vest reports 60 at t0 + 40 and 0 at t0 + 150
+  });
+
+  it("should reject curve points that decrease or miss the amount", async () => {
+    // test logic for init with a decreasing cumulative amount, with non-increasing timestamps, with a single point and with a last cumulative amount below the amount, asserting InvalidArgument each time
+  });
+
+  it("should reject a schedule longer than the configured max_duration", async () => {
+    // test logic for configuring a max_duration of 100 years, initializing a schedule ending 101 years from now and asserting DurationTooLong
+  });
+
+  it("should accept a schedule within the configured max_duration", async () => {
+    // test logic for configuring a max_duration of 100 years, initializing a 4-year schedule and asserting it is created with the requested end
+  });
+
+  it("should split a claim between the treasury, the tax account and the recipient", async () => {
+    // test logic for configuring a 100 bps claim fee, initializing a schedule of 1000 tokens with withhold_bps 2000 and a tax account, warping past vesting_end, claiming and asserting the treasury received 10, the tax account 198 and the recipient 792
+  });
+
+  it("should reject a withholding claim without the tax account", async () => {
+    // test logic for a schedule withholding 2000 bps, claiming with another token account in place of the tax account and asserting InvalidAccountData, then initializing with withhold_bps 2000 and the default tax account and asserting InvalidArgument
+  });
+
+  it("should reject init once the program is deprecated", async () => {
+    // test logic for updating the config with deprecated set, initializing a schedule and asserting Deprecated
+  });
+
+  it("should still claim an existing schedule once the program is deprecated", async () => {
+    // test logic for initializing a schedule, updating the config with deprecated set, warping past vesting_end, claiming and asserting the full amount is transferred
+  });
+
+  it("should claim the rest early with the penalty sent to the funder", async () => {
+    // test logic for initializing a linear schedule of 1000 tokens with allow_early_claim and early_claim_penalty_bps 1000, warping 40% through, calling claim_early signed by the receiver and asserting the funder received 60 (10% of the 600 unvested), the recipient 940 and the schedule is closed out with amount and claimed_amount 940
+  });
+
+  it("should reject an early claim of a schedule that did not opt in", async () => {
+    // test logic for initializing a schedule without allow_early_claim, calling claim_early and asserting InvalidArgument, then calling it on an opted-in schedule without the receiver's signature and asserting MissingRequiredSignature
+  });
+
+  it("should claim a governed schedule through the governance program", async () => {
+    // test logic for initializing a schedule with claim_governance set to a mock governance program, warping past vesting_end, sending a transaction to the mock program that invokes claim_vesting through CPI with the Instructions sysvar and asserting the full amount is transferred
+  });
+
+  it("should reject a direct claim of a governed schedule", async () => {
+    // test logic for initializing a schedule with claim_governance set, claiming directly with the Instructions sysvar and asserting GovernanceRequired, then claiming through another program's CPI and asserting GovernanceRequired
+  });
 });